@@ -0,0 +1,16 @@
+CREATE VIRTUAL TABLE IF NOT EXISTS StudentSearch USING fts5(
+    StudentId UNINDEXED,
+    Name,
+    Email,
+    Department,
+    Office,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO StudentSearch (StudentId, Name, Email, Department, Office)
+SELECT Students.Id, Students.Name, Students.Email, Students.Department,
+    group_concat(Offices.Building || ' ' || Offices.Room, ' ')
+FROM Students
+LEFT JOIN Offices
+ON Students.Id = Offices.StudentId
+GROUP BY Students.Id;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS ResponsesByPurdueEmail;
+
+ALTER TABLE Responses DROP COLUMN PurdueEmail;
@@ -0,0 +1,5 @@
+ALTER TABLE Responses ADD COLUMN PurdueEmail VARCHAR;
+
+CREATE UNIQUE INDEX IF NOT EXISTS ResponsesByPurdueEmail ON Responses (
+    PurdueEmail
+);
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS Users (
+    Username VARCHAR PRIMARY KEY,
+    PasswordHash VARCHAR NOT NULL,
+    Role VARCHAR NOT NULL,
+    CreatedAt INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS Sessions (
+    Token VARCHAR PRIMARY KEY,
+    Username VARCHAR NOT NULL,
+    CreatedAt INTEGER,
+    ExpiresAt INTEGER,
+    FOREIGN KEY(Username) REFERENCES Users(Username)
+);
+
+CREATE INDEX IF NOT EXISTS SessionsByUsername ON Sessions (
+    Username
+);
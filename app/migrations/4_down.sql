@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS PipelineRunColleges;
+DROP TABLE IF EXISTS PipelineRuns;
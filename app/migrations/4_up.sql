@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS PipelineRuns (
+    RunId VARCHAR PRIMARY KEY,
+    StartedAt INTEGER,
+    EndedAt INTEGER,
+    Status VARCHAR
+);
+
+CREATE TABLE IF NOT EXISTS PipelineRunColleges (
+    RunId VARCHAR,
+    CollegeId VARCHAR,
+    Scraped INTEGER,
+    Errors INTEGER,
+    Inserted INTEGER,
+    Updated INTEGER,
+    PRIMARY KEY (RunId, CollegeId),
+    FOREIGN KEY(RunId) REFERENCES PipelineRuns(RunId),
+    FOREIGN KEY(CollegeId) REFERENCES College(Id)
+);
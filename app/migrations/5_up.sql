@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS PipelineRunErrors (
+    ErrorId VARCHAR PRIMARY KEY,
+    RunId VARCHAR,
+    CollegeId VARCHAR,
+    Kind VARCHAR,
+    Message TEXT,
+    FOREIGN KEY(RunId) REFERENCES PipelineRuns(RunId),
+    FOREIGN KEY(CollegeId) REFERENCES College(Id)
+);
+
+CREATE INDEX IF NOT EXISTS PipelineRunErrorsByRun ON PipelineRunErrors (
+    RunId,
+    CollegeId
+);
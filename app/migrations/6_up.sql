@@ -0,0 +1,28 @@
+DROP TABLE IF EXISTS PipelineRunErrors;
+
+CREATE TABLE IF NOT EXISTS ScrapeErrors (
+    ErrorId VARCHAR PRIMARY KEY,
+    CollegeId VARCHAR,
+    Page INTEGER,
+    Kind VARCHAR,
+    Message TEXT,
+    Snippet TEXT NOT NULL DEFAULT '',
+    FirstRunId VARCHAR,
+    LastRunId VARCHAR,
+    Occurrences INTEGER,
+    FOREIGN KEY(CollegeId) REFERENCES College(Id),
+    FOREIGN KEY(FirstRunId) REFERENCES PipelineRuns(RunId),
+    FOREIGN KEY(LastRunId) REFERENCES PipelineRuns(RunId)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS ScrapeErrorsByFingerprint ON ScrapeErrors (
+    CollegeId,
+    Kind,
+    Message,
+    Snippet
+);
+
+CREATE INDEX IF NOT EXISTS ScrapeErrorsByLastRun ON ScrapeErrors (
+    LastRunId,
+    CollegeId
+);
@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS StudentHistory (
+    StudentId VARCHAR,
+    Version INTEGER,
+    Name VARCHAR,
+    Email VARCHAR,
+    Department VARCHAR,
+    CollegeId VARCHAR,
+    FirstSeenAt INTEGER,
+    LastSeenAt INTEGER,
+    LastSeenRunId VARCHAR,
+    ChangedFields VARCHAR,
+    AbsentSince INTEGER,
+    PRIMARY KEY (StudentId, Version),
+    FOREIGN KEY(StudentId) REFERENCES Students(Id),
+    FOREIGN KEY(CollegeId) REFERENCES College(Id),
+    FOREIGN KEY(LastSeenRunId) REFERENCES PipelineRuns(RunId)
+);
+
+CREATE INDEX IF NOT EXISTS StudentHistoryByCollege ON StudentHistory (
+    CollegeId
+);
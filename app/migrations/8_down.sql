@@ -0,0 +1,7 @@
+DROP TABLE IF EXISTS AmbiguousSalaryCandidates;
+DROP TABLE IF EXISTS AmbiguousSalaries;
+
+ALTER TABLE Salaries DROP COLUMN Method;
+ALTER TABLE Salaries DROP COLUMN Confidence;
+ALTER TABLE Salaries DROP COLUMN SalaryDepartment;
+ALTER TABLE Salaries DROP COLUMN SalaryName;
@@ -0,0 +1,24 @@
+ALTER TABLE Salaries ADD COLUMN SalaryName VARCHAR;
+ALTER TABLE Salaries ADD COLUMN SalaryDepartment VARCHAR;
+ALTER TABLE Salaries ADD COLUMN Confidence REAL;
+ALTER TABLE Salaries ADD COLUMN Method VARCHAR;
+
+CREATE TABLE IF NOT EXISTS AmbiguousSalaries (
+    Year INTEGER,
+    Name VARCHAR,
+    Department VARCHAR,
+    AmountUsd INTEGER,
+    PRIMARY KEY (Year, Name, Department)
+);
+
+CREATE TABLE IF NOT EXISTS AmbiguousSalaryCandidates (
+    Year INTEGER,
+    Name VARCHAR,
+    Department VARCHAR,
+    StudentId VARCHAR,
+    Confidence REAL,
+    Method VARCHAR,
+    PRIMARY KEY (Year, Name, Department, StudentId),
+    FOREIGN KEY(Year, Name, Department) REFERENCES AmbiguousSalaries(Year, Name, Department),
+    FOREIGN KEY(StudentId) REFERENCES Students(Id)
+);
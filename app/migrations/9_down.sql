@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS SalaryMatchOverrides;
+DROP TABLE IF EXISTS UnmatchedSalaries;
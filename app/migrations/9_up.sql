@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS UnmatchedSalaries (
+    Year INTEGER,
+    Name VARCHAR,
+    Department VARCHAR,
+    AmountUsd INTEGER,
+    PRIMARY KEY (Year, Name, Department)
+);
+
+CREATE TABLE IF NOT EXISTS SalaryMatchOverrides (
+    Year INTEGER,
+    Name VARCHAR,
+    Department VARCHAR,
+    StudentId VARCHAR,
+    Action VARCHAR,
+    UpdatedAt INTEGER,
+    PRIMARY KEY (Year, Name, Department),
+    FOREIGN KEY(StudentId) REFERENCES Students(Id)
+);
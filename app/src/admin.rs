@@ -0,0 +1,161 @@
+use std::{
+    io::Cursor,
+    str::FromStr,
+    sync::{atomic::Ordering, Arc},
+};
+
+use askama::Template;
+use rusqlite::{Connection, OptionalExtension};
+use tiny_http::{Header, Request, Response};
+
+use crate::{
+    error::Status,
+    history::{fetch_department_changes, DepartmentChange},
+    registry::CollegeSource,
+    server::ServerState,
+};
+
+const DEPARTMENT_CHANGES_LIMIT: usize = 50;
+
+#[derive(Template)]
+#[template(path = "pipeline_dashboard.html")]
+pub struct PipelineDashboard {
+    pub workers: usize,
+    pub live_workers: usize,
+    pub runs: Vec<PipelineRunRow>,
+    pub colleges: Vec<CollegeScrapeHealth>,
+    pub department_changes: Vec<DepartmentChange>,
+}
+
+pub struct PipelineRunRow {
+    pub started_at: String,
+    pub ended_at: String,
+    pub status: String,
+}
+
+pub struct CollegeScrapeHealth {
+    pub id: String,
+    pub name: String,
+    pub last_scraped_at: String,
+    pub student_count: usize,
+    pub scraped_count: usize,
+    pub error_count: usize,
+    pub errors: Vec<ScrapeErrorRow>,
+}
+
+pub struct ScrapeErrorRow {
+    pub kind: String,
+    pub message: String,
+    pub page: String,
+    pub snippet: String,
+    pub occurrences: usize,
+}
+
+// Renders the scrape health of every configured college from the most recent pipeline runs
+pub fn display_pipeline_dashboard(
+    _request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let connection = context.connection_pool.get()?;
+
+    Ok(Response::from_string(
+        PipelineDashboard {
+            workers: context.configuration.workers,
+            live_workers: context.live_workers.load(Ordering::SeqCst),
+            runs: fetch_recent_runs(&connection)?,
+            colleges: context
+                .sources
+                .iter()
+                .map(|source| fetch_college_health(source, &connection))
+                .collect::<Result<Vec<CollegeScrapeHealth>, Status>>()?,
+            department_changes: fetch_department_changes(&connection, DEPARTMENT_CHANGES_LIMIT)?,
+        }
+        .to_string(),
+    )
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
+}
+
+fn fetch_recent_runs(connection: &Connection) -> Result<Vec<PipelineRunRow>, Status> {
+    let mut runs_statement = connection.prepare(
+        "SELECT datetime(StartedAt, 'unixepoch') AS StartedAt,
+                datetime(EndedAt, 'unixepoch') AS EndedAt, Status
+            FROM PipelineRuns
+            ORDER BY PipelineRuns.StartedAt DESC
+            LIMIT 10",
+    )?;
+    let mut runs_query = runs_statement.query([])?;
+    let mut runs = vec![];
+
+    while let Some(row) = runs_query.next()? {
+        runs.push(PipelineRunRow {
+            started_at: row.get("StartedAt")?,
+            ended_at: row.get::<_, Option<String>>("EndedAt")?.unwrap_or_default(),
+            status: row.get("Status")?,
+        });
+    }
+
+    Ok(runs)
+}
+
+fn fetch_college_health(
+    source: &CollegeSource,
+    connection: &Connection,
+) -> Result<CollegeScrapeHealth, Status> {
+    let student_count: usize = connection.query_row(
+        "SELECT COUNT(*) FROM Students WHERE CollegeId = ?1",
+        [&source.college.id],
+        |row| row.get(0),
+    )?;
+    let mut health = CollegeScrapeHealth {
+        id: source.college.id.clone(),
+        name: source.college.name.clone(),
+        last_scraped_at: String::from("Never"),
+        student_count,
+        scraped_count: 0,
+        error_count: 0,
+        errors: vec![],
+    };
+    let last_run: Option<(String, String, usize, usize)> = connection
+        .query_row(
+            "SELECT PipelineRuns.RunId, datetime(PipelineRuns.StartedAt, 'unixepoch'),
+                Scraped, Errors
+            FROM PipelineRunColleges
+            JOIN PipelineRuns
+            ON PipelineRuns.RunId = PipelineRunColleges.RunId
+            WHERE CollegeId = ?1
+            ORDER BY PipelineRuns.StartedAt DESC
+            LIMIT 1",
+            [&source.college.id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()?;
+
+    let Some((run_id, started_at, scraped_count, error_count)) = last_run else {
+        return Ok(health);
+    };
+    let mut errors_statement = connection.prepare(
+        "SELECT Kind, Message, Page, Snippet, Occurrences FROM ScrapeErrors
+            WHERE LastRunId = ?1 AND CollegeId = ?2
+            ORDER BY Occurrences DESC, Kind ASC",
+    )?;
+    let mut errors_query = errors_statement.query([&run_id, &source.college.id])?;
+
+    while let Some(row) = errors_query.next()? {
+        health.errors.push(ScrapeErrorRow {
+            kind: row.get("Kind")?,
+            message: row.get("Message")?,
+            page: row
+                .get::<_, Option<usize>>("Page")?
+                .map(|page| page.to_string())
+                .unwrap_or_default(),
+            snippet: row.get("Snippet")?,
+            occurrences: row.get("Occurrences")?,
+        });
+    }
+
+    health.last_scraped_at = started_at;
+    health.scraped_count = scraped_count;
+    health.error_count = error_count;
+
+    Ok(health)
+}
@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     college::{GraduateStudent, Office},
-    error::Status,
+    error::{RowError, Status},
     scraper::{PagedRequest, PagedResponse, StudentScraper},
 };
 
@@ -140,7 +140,10 @@ impl StudentScraper<ListAgricultureStaffDirectoryRequest, ListAgricultureStaffDi
                 let mut names = vec![];
 
                 if student.id.is_none() && student.email.is_none() {
-                    return Some(Err(Status::NotFound(anyhow!("No id or email was found"))));
+                    return Some(Err(Status::NotFound(Error::from(RowError::new(
+                        "No id or email was found",
+                        serde_json::to_string(&student).unwrap_or_default(),
+                    )))));
                 }
 
                 let id = match student.id {
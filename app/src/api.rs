@@ -0,0 +1,330 @@
+use std::{io::Cursor, str::FromStr, sync::Arc};
+
+use anyhow::anyhow;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Request, Response};
+
+use crate::{
+    college::{read_graduate_student, GraduateStudent},
+    directory::{fetch_directory_students, Pagination},
+    error::Status,
+    http::extract_query,
+    salary::{format_usd, read_salary, Salary},
+    server::ServerState,
+};
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+const RESOURCES: [&str; 3] = [
+    "/api/v1/students",
+    "/api/v1/colleges",
+    "/api/v1/departments",
+];
+
+#[derive(Serialize, Debug)]
+pub struct ApiItem<T> {
+    pub data: T,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ApiList<T> {
+    pub data: Vec<T>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ApiPage<T> {
+    pub data: Vec<T>,
+    pub pagination: Pagination,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ApiError {
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StudentResource {
+    #[serde(flatten)]
+    pub student: GraduateStudent,
+    pub college_id: String,
+    pub salaries: Vec<SalaryResource>,
+}
+
+// Salaries are stored in cents, so the api spells out the unit next to a formatted amount
+#[derive(Serialize, Debug)]
+pub struct SalaryResource {
+    pub student_id: String,
+    pub year: usize,
+    pub amount_cents: usize,
+    pub amount_usd: String,
+    pub salary_name: String,
+    pub salary_department: String,
+    pub confidence: f64,
+    pub method: String,
+}
+
+impl From<Salary> for SalaryResource {
+    fn from(salary: Salary) -> Self {
+        SalaryResource {
+            student_id: salary.student_id,
+            year: salary.year,
+            amount_cents: salary.amount_usd,
+            amount_usd: format_usd(salary.amount_usd),
+            salary_name: salary.salary_name,
+            salary_department: salary.salary_department,
+            confidence: salary.confidence,
+            method: salary.method,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct CollegeResource {
+    pub id: String,
+    pub name: String,
+    pub student_count: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CollegeDetailResource {
+    #[serde(flatten)]
+    pub college: CollegeResource,
+    pub departments: Vec<DepartmentResource>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DepartmentResource {
+    pub name: String,
+    pub college_id: String,
+    pub student_count: usize,
+}
+
+#[derive(Deserialize, Debug)]
+struct PageSizeQuery {
+    page_size: Option<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DepartmentsQuery {
+    college_id: Option<String>,
+}
+
+// Routes the read only JSON api under /api/v1
+pub fn route_api(
+    request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let path = request.url().split('?').next().unwrap();
+    let segments: Vec<&str> = path
+        .trim_start_matches("/api/v1")
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let connection = context.connection_pool.get()?;
+
+    match segments.as_slice() {
+        [] => Ok(json_response(&ApiList {
+            data: RESOURCES.to_vec(),
+        })),
+        ["students"] => list_students(request, &connection),
+        ["students", student_id] => get_student(student_id, &connection),
+        ["students", student_id, "salaries"] => list_student_salaries(student_id, &connection),
+        ["colleges"] => list_colleges(&connection),
+        ["colleges", college_id] => get_college(college_id, &connection),
+        ["departments"] => list_departments(request, &connection),
+        _ => Err(Status::NotFound(anyhow!("Unknown api route {}", path))),
+    }
+}
+
+pub fn json_response(body: &impl Serialize) -> Response<Cursor<Vec<u8>>> {
+    Response::from_string(serde_json::to_string(body).unwrap())
+        .with_header(Header::from_str("Content-Type: application/json").unwrap())
+}
+
+pub fn json_error_response(status: &Status, message: String) -> Response<Cursor<Vec<u8>>> {
+    json_response(&ApiError {
+        kind: status.kind().to_string(),
+        message,
+    })
+}
+
+// Lists the students with their salaries, taking the same filters, search, sort and page as
+// the directory page along with a page_size
+fn list_students(
+    request: &Request,
+    connection: &Connection,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let page_size = extract_query::<PageSizeQuery>(request.url())
+        .map_err(|error| Status::InvalidArgument(anyhow!("Failed to parse page size: {}", error)))?
+        .page_size
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let (students, pagination) = fetch_directory_students(request.url(), page_size, connection)?;
+    let data = students
+        .into_iter()
+        .map(|(student, college_id)| {
+            Ok(StudentResource {
+                salaries: fetch_student_salaries(&student.id, connection)?,
+                student,
+                college_id,
+            })
+        })
+        .collect::<Result<Vec<StudentResource>, Status>>()?;
+
+    Ok(json_response(&ApiPage { data, pagination }))
+}
+
+fn get_student(
+    student_id: &str,
+    connection: &Connection,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let (student, college_id) = fetch_student(student_id, connection)?;
+
+    Ok(json_response(&ApiItem {
+        data: StudentResource {
+            student,
+            college_id,
+            salaries: fetch_student_salaries(student_id, connection)?,
+        },
+    }))
+}
+
+fn list_student_salaries(
+    student_id: &str,
+    connection: &Connection,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    fetch_student(student_id, connection)?;
+
+    Ok(json_response(&ApiList {
+        data: fetch_student_salaries(student_id, connection)?,
+    }))
+}
+
+fn list_colleges(connection: &Connection) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let mut statement = connection.prepare(
+        "SELECT College.Id, College.Name, COUNT(Students.Id) AS StudentCount
+            FROM College
+            LEFT JOIN Students
+            ON College.Id = Students.CollegeId
+            GROUP BY College.Id
+            ORDER BY College.Name ASC",
+    )?;
+    let colleges = statement
+        .query_map([], |row| {
+            Ok(CollegeResource {
+                id: row.get("Id")?,
+                name: row.get("Name")?,
+                student_count: row.get("StudentCount")?,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<CollegeResource>, rusqlite::Error>>())?;
+
+    Ok(json_response(&ApiList { data: colleges }))
+}
+
+fn get_college(
+    college_id: &str,
+    connection: &Connection,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let college = connection
+        .query_row(
+            "SELECT College.Id, College.Name, COUNT(Students.Id) AS StudentCount
+            FROM College
+            LEFT JOIN Students
+            ON College.Id = Students.CollegeId
+            WHERE College.Id = ?1
+            GROUP BY College.Id",
+            [college_id],
+            |row| {
+                Ok(CollegeResource {
+                    id: row.get("Id")?,
+                    name: row.get("Name")?,
+                    student_count: row.get("StudentCount")?,
+                })
+            },
+        )
+        .optional()?
+        .ok_or_else(|| Status::NotFound(anyhow!("Unknown college '{}'", college_id)))?;
+
+    Ok(json_response(&ApiItem {
+        data: CollegeDetailResource {
+            college,
+            departments: fetch_departments(Some(college_id), connection)?,
+        },
+    }))
+}
+
+fn list_departments(
+    request: &Request,
+    connection: &Connection,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let query: DepartmentsQuery = extract_query(request.url()).map_err(|error| {
+        Status::InvalidArgument(anyhow!("Failed to parse departments query: {}", error))
+    })?;
+
+    Ok(json_response(&ApiList {
+        data: fetch_departments(query.college_id.as_deref(), connection)?,
+    }))
+}
+
+fn fetch_student(
+    student_id: &str,
+    connection: &Connection,
+) -> Result<(GraduateStudent, String), Status> {
+    connection
+        .query_row(
+            "SELECT Id, Name, Email, Department, CollegeId, Building, Room
+            FROM Students
+            LEFT JOIN Offices
+            ON Students.Id = Offices.StudentId
+            WHERE Id = ?1
+            LIMIT 1",
+            [student_id],
+            |row| Ok((read_graduate_student(row)?, row.get("CollegeId")?)),
+        )
+        .optional()?
+        .ok_or_else(|| Status::NotFound(anyhow!("Unknown student '{}'", student_id)))
+}
+
+fn fetch_student_salaries(
+    student_id: &str,
+    connection: &Connection,
+) -> Result<Vec<SalaryResource>, Status> {
+    let mut statement = connection.prepare(
+        "SELECT StudentId, Year, AmountUsd, SalaryName, SalaryDepartment, Confidence, Method
+            FROM Salaries
+            WHERE StudentId = ?1
+            ORDER BY Year ASC",
+    )?;
+    let salaries = statement
+        .query_map([student_id], read_salary)
+        .and_then(|rows| rows.collect::<Result<Vec<Salary>, rusqlite::Error>>())?;
+
+    Ok(salaries.into_iter().map(SalaryResource::from).collect())
+}
+
+fn fetch_departments(
+    college_id: Option<&str>,
+    connection: &Connection,
+) -> Result<Vec<DepartmentResource>, Status> {
+    let mut statement = connection.prepare(
+        "SELECT Department, CollegeId, COUNT(*) AS StudentCount
+            FROM Students
+            WHERE ?1 IS NULL OR CollegeId = ?1
+            GROUP BY Department, CollegeId
+            ORDER BY Department ASC, CollegeId ASC",
+    )?;
+    let departments = statement
+        .query_map([college_id], |row| {
+            Ok(DepartmentResource {
+                name: row.get("Department")?,
+                college_id: row.get("CollegeId")?,
+                student_count: row.get("StudentCount")?,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<DepartmentResource>, rusqlite::Error>>())?;
+
+    Ok(departments)
+}
@@ -0,0 +1,282 @@
+use std::{fmt::Display, io::Cursor, str::FromStr, sync::Arc};
+
+use anyhow::anyhow;
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use askama::Template;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use tiny_http::{Header, Method, Request, Response, StatusCode};
+
+use crate::{
+    error::Status,
+    http::{extract_query, find_header, parse_form_data, require_shared_secret},
+    id::generate_id,
+    pipeline::unix_timestamp,
+    server::ServerState,
+};
+
+const SESSION_COOKIE: &str = "session";
+const SESSION_SECONDS: u64 = 60 * 60 * 24 * 7;
+
+#[derive(Template)]
+#[template(path = "login.html")]
+pub struct LoginPage {
+    pub next: String,
+}
+
+// Roles are ordered so a role can do everything the roles before it can
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Viewer,
+    Organizer,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Organizer => "organizer",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role.to_lowercase().as_str() {
+            "viewer" => Ok(Role::Viewer),
+            "organizer" => Ok(Role::Organizer),
+            "admin" => Ok(Role::Admin),
+            _ => Err(anyhow!("Unknown role '{}'", role)),
+        }
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct User {
+    pub username: String,
+    pub role: Role,
+}
+
+#[derive(Deserialize, Debug)]
+struct LoginQuery {
+    next: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LoginRequest {
+    username: String,
+    password: String,
+    next: Option<String>,
+}
+
+// The role a route needs, where routes outside of /admin are public
+pub fn required_role(method: &Method, path: &str) -> Option<Role> {
+    match (method, path) {
+        (Method::Get, "/admin/pipeline") => Some(Role::Viewer),
+        (_, path) if path.starts_with("/admin/responses") => Some(Role::Organizer),
+        (_, path) if path.starts_with("/admin/") => Some(Role::Admin),
+        _ => None,
+    }
+}
+
+// Checks the session of the request against the role the route needs before the route runs
+pub fn authorize(request: &Request, context: &Arc<ServerState>) -> Result<(), Status> {
+    let path = request.url().split('?').next().unwrap_or_default();
+    let Some(role) = required_role(request.method(), path) else {
+        return Ok(());
+    };
+    let connection = context.connection_pool.get()?;
+    let Some(user) = find_session_user(request, &connection)? else {
+        // Organizers given the shared secret before accounts existed can keep sending it
+        let shared_secret = &context.configuration.organizer.shared_secret;
+
+        if role == Role::Organizer && require_shared_secret(request, shared_secret).is_ok() {
+            return Ok(());
+        }
+
+        return Err(Status::Unauthenticated(anyhow!("Sign in to continue")));
+    };
+
+    if user.role < role {
+        return Err(Status::PermissionDenied(anyhow!(
+            "{} needs the {} role",
+            path,
+            role
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn find_session_user(
+    request: &Request,
+    connection: &Connection,
+) -> Result<Option<User>, Status> {
+    let Some(token) = find_session_token(request) else {
+        return Ok(None);
+    };
+    let user = connection
+        .query_row(
+            "SELECT Users.Username, Role FROM Sessions
+            JOIN Users
+            ON Users.Username = Sessions.Username
+            WHERE Token = ?1 AND ExpiresAt > ?2",
+            params![token, unix_timestamp()],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()?;
+
+    user.map(|(username, role)| {
+        Ok(User {
+            role: role.parse().map_err(Status::Internal)?,
+            username,
+        })
+    })
+    .transpose()
+}
+
+pub fn find_session_token(request: &Request) -> Option<&str> {
+    find_header(request, "Cookie")?
+        .value
+        .as_str()
+        .split(';')
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, token)| token)
+        .filter(|token| !token.is_empty())
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash).is_ok_and(|password_hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &password_hash)
+            .is_ok()
+    })
+}
+
+pub fn display_login(request: &Request) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let query: LoginQuery = extract_query(request.url()).map_err(|error| {
+        Status::InvalidArgument(anyhow!("Failed to parse login query: {}", error))
+    })?;
+
+    Ok(Response::from_string(
+        LoginPage {
+            next: safe_redirect(query.next.as_deref()).to_string(),
+        }
+        .to_string(),
+    )
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
+}
+
+// Starts a session for the user and sends them on to the page that asked them to sign in
+pub fn login(
+    request: &mut Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let login_request: LoginRequest = parse_form_data(request)?;
+    let next = safe_redirect(login_request.next.as_deref());
+    let connection = context.connection_pool.get()?;
+    let password_hash: Option<String> = connection
+        .query_row(
+            "SELECT PasswordHash FROM Users WHERE Username = ?1",
+            [login_request.username.trim()],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    if !password_hash
+        .is_some_and(|password_hash| verify_password(&login_request.password, &password_hash))
+    {
+        return Err(Status::InvalidArgument(anyhow!(
+            "Wrong username or password"
+        )));
+    }
+
+    let token = generate_id();
+    let now = unix_timestamp();
+    let response = redirect_response(request, next)?.with_header(build_header(
+        "Set-Cookie",
+        &format!(
+            "{}={}; Max-Age={}; Path=/; HttpOnly; Secure; SameSite=Lax",
+            SESSION_COOKIE, token, SESSION_SECONDS
+        ),
+    )?);
+    connection.execute("DELETE FROM Sessions WHERE ExpiresAt <= ?1", [now])?;
+    connection.execute(
+        "INSERT INTO Sessions (Token, Username, CreatedAt, ExpiresAt) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            token,
+            login_request.username.trim(),
+            now,
+            now + SESSION_SECONDS
+        ],
+    )?;
+
+    Ok(response)
+}
+
+pub fn logout(
+    request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    if let Some(token) = find_session_token(request) {
+        context
+            .connection_pool
+            .get()?
+            .execute("DELETE FROM Sessions WHERE Token = ?1", [token])?;
+    }
+
+    Ok(redirect_response(request, "/")?.with_header(build_header(
+        "Set-Cookie",
+        &format!(
+            "{}=; Max-Age=0; Path=/; HttpOnly; Secure; SameSite=Lax",
+            SESSION_COOKIE
+        ),
+    )?))
+}
+
+// htmx only follows redirects it is told about in a header
+pub fn redirect_response(
+    request: &Request,
+    location: &str,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    Ok(if find_header(request, "HX-Request").is_some() {
+        Response::from_string("").with_header(build_header("HX-Redirect", location)?)
+    } else {
+        Response::from_string("")
+            .with_status_code(StatusCode::from(303))
+            .with_header(build_header("Location", location)?)
+    })
+}
+
+// Only redirects within the site so the login form can't send users elsewhere. Browsers
+// drop tabs and newlines from urls, so anything but printable ascii could turn "/" into "//"
+pub fn safe_redirect(next: Option<&str>) -> &str {
+    next.filter(|next| {
+        next.starts_with('/')
+            && !next.starts_with("//")
+            && !next.contains('\\')
+            && next.chars().all(|character| character.is_ascii_graphic())
+    })
+    .unwrap_or("/")
+}
+
+// Header values can't carry control characters, which would start a new header line
+fn build_header(field: &str, value: &str) -> Result<Header, Status> {
+    if value.chars().any(|character| character.is_ascii_control()) {
+        return Err(Status::Internal(anyhow!("Invalid {} header value", field)));
+    }
+
+    Header::from_bytes(field.as_bytes(), value.as_bytes())
+        .map_err(|_| Status::Internal(anyhow!("Invalid {} header value", field)))
+}
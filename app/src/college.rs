@@ -1,13 +1,27 @@
-use std::{collections::HashSet, io::Cursor, str::FromStr, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Cursor,
+    str::FromStr,
+    sync::Arc,
+};
 
+use anyhow::anyhow;
 use askama::Template;
-use num_format::{Buffer, Locale};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Connection, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
 use tiny_http::{Header, Request, Response};
 
-use crate::{directory::StudentDirectoryRow, error::Status, id::generate_id, server::ServerState};
+use crate::{
+    directory::{build_college_directory, find_college_id, Directory},
+    error::Status,
+    http::extract_query,
+    id::generate_id,
+    salary::format_usd,
+    server::ServerState,
+    statistics::{summarize_sorted, CompensationSummary},
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
 pub struct Office {
@@ -15,11 +29,12 @@ pub struct Office {
     pub room: String,
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Deserialize)]
 pub struct College {
     pub id: String,
     pub name: String,
     pub base_url: String,
+    #[serde(default)]
     pub default_office: Office,
     pub default_department: String,
 }
@@ -33,183 +48,273 @@ pub struct GraduateStudent {
     pub office: Office,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StoreCounts {
+    pub inserted: usize,
+    pub updated: usize,
+}
+
+#[derive(Deserialize, Debug)]
+struct CollegePageQuery {
+    year: Option<usize>,
+}
+
 #[derive(Template)]
 #[template(path = "college_page.html")]
 pub struct CollegePage {
     pub college: College,
-    pub students: Vec<StudentDirectoryRow>,
+    pub year: Option<usize>,
+    pub departments: Vec<DepartmentSummary>,
+    pub directory: Directory,
 }
 
-// Renders a page with information about the college and all graduate students in the college
+pub struct DepartmentSummary {
+    pub name: String,
+    pub student_count: usize,
+    pub salary_count: usize,
+    pub min: String,
+    pub median: String,
+    pub max: String,
+}
+
+// Renders the college's departments with their compensation for the selected or latest
+// salary year above the directory scoped to the college
 pub fn display_college(
     request: &Request,
-    context: &Arc<ServerState>
-) -> Response<Cursor<Vec<u8>>> {
-    let college_id = request.url().split("/college/").skip(1).next().unwrap();
-    let connection = context.connection_pool.get().unwrap();
-    let mut college_statement = connection
-        .prepare("SELECT Id, Name, Url FROM Colleges WHERE Id = ?1")
-        .unwrap();
-    let mut students_statement = connection
-        .prepare(
-            "SELECT Id, Department, Email, Name, Office, Year, AmountUsd, CollegeId
-                 FROM Students 
-                 JOIN Salaries 
-                 ON Students.Id = Salaries.StudentId AND Students.CollegeId = ?1 ORDER BY Id ASC
-                 JOIN Offices
-                 ON Students.Id = Offices.StudentId",
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let path = request.url().split('?').next().unwrap_or_default();
+    let college_id =
+        find_college_id(path).ok_or_else(|| Status::NotFound(anyhow!("Missing college id")))?;
+    let connection = context.connection_pool.get()?;
+    let college = connection
+        .query_row(
+            "SELECT Id, Name FROM College WHERE Id = ?1",
+            [college_id],
+            |row| {
+                Ok(College {
+                    id: row.get("Id")?,
+                    name: row.get("Name")?,
+                    ..College::default()
+                })
+            },
         )
-        .unwrap();
-    let mut students = vec![];
-    let mut student_query = students_statement.query(&[&college_id]).unwrap();
-    let college = college_statement
-        .query_row(&[&college_id], |row| {
-            let mut college = College::default();
-            college.name = row.get("Name").unwrap();
-            college.id = row.get("Id").unwrap();
-
-            Ok(college)
-        })
-        .unwrap();
+        .optional()?
+        .ok_or_else(|| Status::NotFound(anyhow!("Unknown college '{}'", college_id)))?;
+    let query: CollegePageQuery = extract_query(request.url()).map_err(|error| {
+        Status::InvalidArgument(anyhow!("Failed to parse college page query: {}", error))
+    })?;
+    let year = match query.year {
+        Some(year) => Some(year),
+        None => connection.query_row(
+            "SELECT MAX(Year) FROM Salaries
+            JOIN Students
+            ON Students.Id = Salaries.StudentId
+            WHERE CollegeId = ?1",
+            [college_id],
+            |row| row.get(0),
+        )?,
+    };
 
-    while let Ok(Some(row)) = student_query.next() {
-        let name: String = row.get("Name").unwrap();
-        let year: usize = row.get("Year").unwrap();
-        let yearly_compensation: usize = row.get("AmountUsd").unwrap();
-        let dollars = yearly_compensation / 100;
-        let cents = yearly_compensation % 100;
-        let mut compensation_buffer = Buffer::default();
-        compensation_buffer.write_formatted(&dollars, &Locale::en);
-
-        students.push(StudentDirectoryRow {
-            id: row.get("Id").unwrap(),
-            department: row.get("Department").unwrap(),
-            college_id: row.get("CollegeId").unwrap(),
-            email: row.get("Email").unwrap(),
-            name: name
-                .split(", ")
-                .map(|part| part.to_string())
-                .collect::<Vec<String>>()
-                .join(" "),
-            office: Office {
-                building: row.get("Building").unwrap(),
-                room: row.get("Room").unwrap(),
-            },
-            yearly_compensation: format!("${}.{}", compensation_buffer.to_string(), cents),
+    Ok(Response::from_string(
+        CollegePage {
+            departments: fetch_department_summaries(college_id, year, &connection)?,
+            directory: build_college_directory(request.url(), college_id, &connection)?,
+            college,
             year,
+        }
+        .to_string(),
+    )
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
+}
+
+fn fetch_department_summaries(
+    college_id: &str,
+    year: Option<usize>,
+    connection: &Connection,
+) -> Result<Vec<DepartmentSummary>, Status> {
+    let mut salaries_statement = connection.prepare(
+        "SELECT Department, AmountUsd
+        FROM Students
+        JOIN Salaries
+        ON Students.Id = Salaries.StudentId
+        WHERE CollegeId = ?1 AND Year = ?2
+        ORDER BY AmountUsd ASC",
+    )?;
+    let mut amounts: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut salaries_query = salaries_statement.query(params![college_id, year])?;
+
+    while let Some(row) = salaries_query.next()? {
+        amounts
+            .entry(row.get("Department")?)
+            .or_default()
+            .push(row.get("AmountUsd")?);
+    }
+
+    let mut departments_statement = connection.prepare(
+        "SELECT Department, COUNT(*) AS StudentCount
+        FROM Students
+        WHERE CollegeId = ?1
+        GROUP BY Department
+        ORDER BY Department ASC",
+    )?;
+    let mut departments_query = departments_statement.query([college_id])?;
+    let mut departments = vec![];
+
+    while let Some(row) = departments_query.next()? {
+        let name: String = row.get("Department")?;
+        let summary = amounts
+            .get(&name)
+            .and_then(|amounts| summarize_sorted(amounts));
+        let format = |amount: fn(&CompensationSummary) -> usize| {
+            summary
+                .as_ref()
+                .map(|summary| format_usd(amount(summary)))
+                .unwrap_or_else(|| String::from("-"))
+        };
+
+        departments.push(DepartmentSummary {
+            student_count: row.get("StudentCount")?,
+            salary_count: summary.map(|summary| summary.count).unwrap_or_default(),
+            min: format(|summary| summary.min),
+            median: format(|summary| summary.median),
+            max: format(|summary| summary.max),
+            name,
         });
     }
 
-    Response::from_string(CollegePage { college, students }.to_string())
-        .with_header(Header::from_str("Content-Type: text/html").unwrap())
+    Ok(departments)
 }
 
-pub fn get_student_by_name(
-    names: &Vec<String>,
-    connection_pool: &Pool<SqliteConnectionManager>,
-) -> Option<GraduateStudent> {
-    let connection = connection_pool.get().unwrap();
-    let mut names = names.clone();
-    let mut name = names.join("%").replace("'", "''");
-    let mut student = connection
-        .query_row(
-            "SELECT Id, Email, Name, Department, Building, Room FROM Students
-            JOIN Offices
-            ON Students.Id = Offices.StudentId
-            WHERE Name LIKE ?1",
-            &[&name],
-            |row| {
-                let name: String = row.get("Name").unwrap();
-
-                Ok(GraduateStudent {
-                    id: row.get("Id").unwrap(),
-                    department: row.get("Department").unwrap(),
-                    email: row.get("Email").unwrap(),
-                    names: name.split(", ").map(|part| part.to_string()).collect(),
-                    office: Office {
-                        building: row.get("Building").unwrap(),
-                        room: row.get("Room").unwrap(),
-                    },
-                })
-            },
+// Reads a student from a row with the Students columns and the Building and Room of their
+// office, which are missing when the student has no office
+pub fn read_graduate_student(row: &Row) -> Result<GraduateStudent, rusqlite::Error> {
+    let name: String = row.get("Name")?;
+
+    Ok(GraduateStudent {
+        id: row.get("Id")?,
+        names: name
+            .split_whitespace()
+            .map(|name| name.to_string())
+            .collect(),
+        email: row.get("Email")?,
+        department: row.get("Department")?,
+        office: Office {
+            building: row
+                .get::<_, Option<String>>("Building")?
+                .unwrap_or_default(),
+            room: row.get::<_, Option<String>>("Room")?.unwrap_or_default(),
+        },
+    })
+}
+
+pub fn store_college(college: &College, connection_pool: &Pool<SqliteConnectionManager>) {
+    connection_pool
+        .get()
+        .unwrap()
+        .execute(
+            "INSERT INTO College (Id, Name) VALUES (?1, ?2)
+            ON CONFLICT(Id) DO UPDATE SET Name = excluded.Name",
+            [&college.id, &college.name],
         )
-        .ok();
-
-    while student.is_none() && names.len() > 2 {
-        names.remove(1);
-        name = names.join("%").replace("'", "''");
-        student = connection
-            .query_row(
-                "SELECT Id, Email, Name, Department, Building, Room FROM Students
-                JOIN Offices
-                ON Students.Id = Offices.StudentId
-                WHERE Name LIKE ?1",
-                &[&name],
-                |row| {
-                    let name: String = row.get("Name").unwrap();
-
-                    Ok(GraduateStudent {
-                        id: row.get("Id").unwrap(),
-                        department: row.get("Department").unwrap(),
-                        email: row.get("Email").unwrap(),
-                        names: name.split(", ").map(|part| part.to_string()).collect(),
-                        office: Office {
-                            building: row.get("Building").unwrap(),
-                            room: row.get("Room").unwrap(),
-                        },
-                    })
-                },
+        .unwrap();
+}
+
+// Stores the students scraped for a college, counting which were new and which known students
+// changed. Known students that are unchanged are left untouched
+pub fn store_students(
+    college: &College,
+    students: &[Result<GraduateStudent, Status>],
+    connection_pool: &Pool<SqliteConnectionManager>,
+) -> StoreCounts {
+    let mut student_ids = fetch_student_ids(connection_pool);
+    let mut counts = StoreCounts::default();
+    let mut connection = connection_pool.get().unwrap();
+    let transaction = connection.transaction().unwrap();
+
+    for student in students.iter().flatten() {
+        let changed = transaction
+            .execute(
+                "INSERT INTO Students (Id, Name, Email, Department, CollegeId)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT (Id) DO UPDATE SET
+                    Name = excluded.Name,
+                    Email = excluded.Email,
+                    Department = excluded.Department,
+                    CollegeId = excluded.CollegeId
+                WHERE Name IS NOT excluded.Name
+                    OR Email IS NOT excluded.Email
+                    OR Department IS NOT excluded.Department
+                    OR CollegeId IS NOT excluded.CollegeId",
+                params![
+                    student.id,
+                    student.names.join(" "),
+                    student.email,
+                    student.department,
+                    college.id
+                ],
             )
-            .ok();
+            .unwrap();
+
+        if student_ids.insert(student.id.clone()) {
+            counts.inserted += 1;
+        } else if changed > 0 {
+            counts.updated += 1;
+        }
     }
 
-    student
+    transaction.commit().unwrap();
+    store_offices(students, connection_pool);
+
+    counts
 }
 
-pub fn store_students(
-    students: &Vec<Result<GraduateStudent, Status>>,
+fn fetch_student_ids(connection_pool: &Pool<SqliteConnectionManager>) -> HashSet<String> {
+    let connection = connection_pool.get().unwrap();
+    let mut student_ids_statement = connection.prepare("SELECT Id FROM Students").unwrap();
+    let mut student_ids_query = student_ids_statement.query([]).unwrap();
+    let mut student_ids = HashSet::new();
+
+    while let Ok(Some(row)) = student_ids_query.next() {
+        student_ids.insert(row.get("Id").unwrap());
+    }
+
+    student_ids
+}
+
+fn store_offices(
+    students: &[Result<GraduateStudent, Status>],
     connection_pool: &Pool<SqliteConnectionManager>,
 ) {
-    for students_chunk in students.chunks(50) {
-        let query = students_chunk
-            .iter()
-            .filter_map(|student| match student {
-                Ok(student) => Some(format!(
-                    "SELECT '{}' AS Id, '{}' AS Name,
-                      '{}' AS Email, '{}' AS Department,
-                      '{}' AS CollegeId\n",
-                    student.id,
-                    student.names.join(" ").replace("'", "''"),
-                    student.email.replace("'", "''"),
-                    student.department.replace("'", "''"),
-                    "1"
-                )),
-                Err(error) => {
-                    eprintln!("{}", error);
-                    None
-                }
-            })
-            .collect::<Vec<String>>()
-            .join("UNION ALL ");
-        connection_pool
-            .get()
-            .unwrap()
+    let student_ids_with_offices = fetch_student_ids_with_offices(connection_pool);
+    let mut connection = connection_pool.get().unwrap();
+    let transaction = connection.transaction().unwrap();
+
+    for student in students
+        .iter()
+        .flatten()
+        .filter(|student| !student_ids_with_offices.contains(&student.id))
+    {
+        transaction
             .execute(
-                &format!(
-                    "INSERT OR REPLACE INTO Students (Id, Name, Email, Department, CollegeId) {query}"
-                ),
-                [],
+                "INSERT OR REPLACE INTO Offices (OfficeId, StudentId, Building, Room)
+                VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    generate_id(),
+                    student.id,
+                    student.office.building,
+                    student.office.room
+                ],
             )
             .unwrap();
     }
 
-    store_offices(students, connection_pool)
+    transaction.commit().unwrap();
 }
 
-fn store_offices(
-    students: &Vec<Result<GraduateStudent, Status>>,
+fn fetch_student_ids_with_offices(
     connection_pool: &Pool<SqliteConnectionManager>,
-) {
+) -> HashSet<String> {
     let connection = connection_pool.get().unwrap();
     let mut students_with_offices_statements = connection
         .prepare(
@@ -225,45 +330,5 @@ fn store_offices(
         student_ids_with_offices.insert(row.get("StudentId").unwrap());
     }
 
-    for students_chunk in students.chunks(50) {
-        let office_rows = students_chunk
-            .iter()
-            .filter_map(|student| match student {
-                Ok(student) => {
-                    if student_ids_with_offices.contains(&student.id) {
-                        return None;
-                    }
-
-                    Some(format!(
-                        "SELECT '{}' AS OfficeId, '{}' AS StudentId,
-                      '{}' AS Building, '{}' AS Room\n",
-                        generate_id(),
-                        student.id,
-                        student.office.building,
-                        student.office.room,
-                    ))
-                }
-                Err(error) => {
-                    eprintln!("{}", error);
-                    None
-                }
-            })
-            .collect::<Vec<String>>();
-        
-        if office_rows.is_empty() {
-            return;
-        }
-
-        let office_query = office_rows.join("UNION ALL ");
-        connection_pool
-            .get()
-            .unwrap()
-            .execute(
-                &format!(
-                    "INSERT OR REPLACE INTO Offices (OfficeId, StudentId, Building, Room) {office_query}"
-                ),
-                [],
-            )
-            .unwrap();
-    }
+    student_ids_with_offices
 }
@@ -1,4 +1,4 @@
-use std::{env::current_dir, fmt::Display, fs::File, path::PathBuf};
+use std::{collections::BTreeMap, env::current_dir, fmt::Display, fs::File, path::PathBuf};
 
 use anyhow::{anyhow, Context, Error};
 use serde::{de::DeserializeOwned, Deserialize};
@@ -95,14 +95,76 @@ fn load_env_file() -> Result<(), Error> {
 pub struct Configuration {
     pub database: DatabaseConfiguration,
     pub files: Files,
+    #[serde(default)]
+    pub organizer: OrganizerConfiguration,
+    #[serde(default)]
+    pub pipeline: PipelineConfiguration,
+    #[serde(default)]
+    pub statistics: StatisticsConfiguration,
     pub port: u32,
     pub host: String,
+    // Number of threads serving requests
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+}
+
+fn default_workers() -> usize {
+    4
+}
+
+#[derive(Deserialize)]
+pub struct PipelineConfiguration {
+    pub interval_seconds: u64,
+}
+
+// Refreshes the directory once a day
+impl Default for PipelineConfiguration {
+    fn default() -> Self {
+        PipelineConfiguration {
+            interval_seconds: 24 * 60 * 60,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct OrganizerConfiguration {
+    // Password organizers without an account can send as basic authorization to see
+    // sign-ups, an empty secret leaves the pages to signed in organizers only
+    #[serde(default)]
+    pub shared_secret: String,
+}
+
+#[derive(Deserialize)]
+pub struct StatisticsConfiguration {
+    // Yearly living wage in whole dollars that salaries are compared against
+    pub living_wage_usd: usize,
+    // Annual average consumer price index by year for inflation adjusted changes
+    pub cpi: BTreeMap<usize, f64>,
+}
+
+// Without a price index every change is reported in nominal dollars only
+impl Default for StatisticsConfiguration {
+    fn default() -> Self {
+        StatisticsConfiguration {
+            living_wage_usd: 35000,
+            cpi: BTreeMap::new(),
+        }
+    }
 }
 
 #[derive(Deserialize)]
 pub struct Files {
-    pub salaries_path: String,
+    // Older configurations name a single export with salaries_path
+    #[serde(alias = "salaries_path")]
+    pub salaries_directory: String,
     pub assets_directory: String,
+    // Defaults to the registry shipped alongside the configuration in the image
+    #[serde(default = "default_sources_path")]
+    pub sources_path: String,
+}
+
+fn default_sources_path() -> String {
+    String::from("data/colleges.json")
 }
 
 #[derive(Deserialize)]
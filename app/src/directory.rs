@@ -1,22 +1,76 @@
-use std::{fmt::Display, io::Cursor, str::FromStr, sync::Arc};
+use std::{
+    fmt::Display,
+    io::{pipe, BufWriter, Cursor, Read, Write},
+    str::FromStr,
+    sync::{
+        mpsc::{channel, Sender},
+        Arc,
+    },
+    thread,
+};
 
+use anyhow::{anyhow, Error};
 use askama::Template;
 use num_format::{Buffer, Locale};
-use rusqlite::{Connection, Statement};
+use rusqlite::{params_from_iter, types::Value, Connection, Row, Rows, Statement};
 use serde::{Deserialize, Serialize};
-use tiny_http::{Header, Request, Response};
+use tiny_http::{Header, Request, Response, StatusCode};
 
 use crate::{
-    college::Office,
-    http::{extract_query, find_header, parse_form_data},
+    college::{read_graduate_student, GraduateStudent, Office},
+    error::Status,
+    filter::{build_filter_condition, ColumnKind, Filter, FilterJoin, FilterOperator},
+    http::{escape_formula, extract_query, parse_form_data, require_header},
+    salary::{fetch_salary_years, format_usd},
+    search::{build_search_match, SEARCH_RANK},
     server::{empty_fragment, ServerState},
 };
 
+const DIRECTORY_PAGE_SIZE: usize = 50;
+const DIRECTORY_TABLES: &str = "FROM Students
+    JOIN Salaries
+    ON Students.Id = Salaries.StudentId
+    LEFT JOIN Offices
+    ON Students.Id = Offices.StudentId";
+const STUDENT_TABLES: &str = "FROM Students
+    LEFT JOIN Offices
+    ON Students.Id = Offices.StudentId";
+const SALARY_COLUMNS: [&str; 2] = ["Year", "AmountUsd"];
+const EXPORT_HEADERS: [&str; 9] = [
+    "Id",
+    "Name",
+    "Email",
+    "Department",
+    "Building",
+    "Room",
+    "Year",
+    "CompensationCents",
+    "CompensationUsd",
+];
+
 #[derive(Template)]
 #[template(path = "directory.html")]
 pub struct Directory {
     pub headings: Vec<DirectoryHeading>,
+    pub page: DirectoryPage,
+    pub export_query: String,
+}
+
+// The table body and page controls, which page changes swap without the headings
+#[derive(Template)]
+#[template(path = "directory_page.html")]
+pub struct DirectoryPage {
     pub rows: Vec<StudentDirectoryRow>,
+    pub pagination: Pagination,
+    pub swap_pagination: bool,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Pagination {
+    pub page: usize,
+    pub page_count: usize,
+    pub page_size: usize,
+    pub total: usize,
 }
 
 #[derive(Template)]
@@ -24,13 +78,22 @@ pub struct Directory {
 pub struct ListStudents {
     pub directory: Directory,
     pub filters: Vec<DirectoryFilter>,
+    pub years: Vec<YearOption>,
+    pub search: String,
+}
+
+pub struct YearOption {
+    pub year: usize,
+    pub selected: bool,
 }
 
 #[derive(Template)]
 #[template(path = "directory_filter.html")]
 pub struct DirectoryFilter {
-    pub column: String,
-    pub value: String,
+    pub filter: Filter,
+    pub join: String,
+    pub formatted_column: String,
+    pub operator: String,
 }
 
 pub struct StudentDirectoryRow {
@@ -56,24 +119,98 @@ pub struct DirectoryHeading {
 #[template(path = "directory_filter_menu.html")]
 pub struct DirectoryFilterMenu {
     pub columns: Vec<Column>,
+    pub selected_column: String,
+    pub operators: Vec<FilterOperator>,
+    pub has_upper_value: bool,
+    pub has_filters: bool,
 }
 
 pub struct Column {
     pub name: String,
     pub formatted_name: String,
+    pub kind: ColumnKind,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Serialize, Debug)]
+pub struct DirectoryExportRow {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    pub department: String,
+    pub building: String,
+    pub room: String,
+    pub year: usize,
+    pub compensation_cents: usize,
+    pub compensation_usd: String,
+}
+
+impl DirectoryExportRow {
+    // The text columns come from scraped pages, so none of them may run as a formula
+    fn escape_formulas(self) -> DirectoryExportRow {
+        DirectoryExportRow {
+            id: escape_formula(&self.id),
+            name: escape_formula(&self.name),
+            email: escape_formula(&self.email),
+            department: escape_formula(&self.department),
+            building: escape_formula(&self.building),
+            room: escape_formula(&self.room),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 struct DirectoryQuery {
-    filters: Option<Vec<String>>,
+    filters: Option<Vec<Filter>>,
     sort_column: Option<String>,
     sort_direction: Option<SortDirection>,
+    year: Option<usize>,
+    search: Option<String>,
+    college_id: Option<String>,
+    page: Option<usize>,
 }
 
 #[derive(Deserialize, Debug)]
 struct CreateDirectoryFilterRequest {
     column: String,
+    operator: FilterOperator,
+    #[serde(default)]
     value: String,
+    upper_value: Option<String>,
+    join: Option<FilterJoin>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DirectoryFilterMenuQuery {
+    column: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SelectDirectoryYearRequest {
+    year: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExportDirectoryQuery {
+    format: Option<ExportFormat>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchDirectoryRequest {
+    #[serde(default)]
+    search: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct PageDirectoryRequest {
+    page: usize,
 }
 
 #[derive(Deserialize, Debug)]
@@ -82,7 +219,7 @@ struct CreateDirectorySortRequest {
     state: SortState,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 enum SortDirection {
     Ascending,
     Descending,
@@ -101,6 +238,45 @@ impl Display for SortState {
     }
 }
 
+impl Pagination {
+    // Clamps the requested page to the pages the total spans, always keeping one page so
+    // an empty directory still renders its controls
+    pub fn new(total: usize, requested_page: Option<usize>, page_size: usize) -> Pagination {
+        let page_count = total.div_ceil(page_size).max(1);
+
+        Pagination {
+            page: requested_page.unwrap_or(1).clamp(1, page_count),
+            page_count,
+            page_size,
+            total,
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        (self.page - 1) * self.page_size
+    }
+
+    pub fn first_row(&self) -> usize {
+        if self.total == 0 {
+            0
+        } else {
+            self.offset() + 1
+        }
+    }
+
+    pub fn last_row(&self) -> usize {
+        (self.offset() + self.page_size).min(self.total)
+    }
+
+    pub fn previous_page(&self) -> Option<usize> {
+        (self.page > 1).then(|| self.page - 1)
+    }
+
+    pub fn next_page(&self) -> Option<usize> {
+        (self.page < self.page_count).then(|| self.page + 1)
+    }
+}
+
 impl SortDirection {
     pub fn to_sql(&self) -> String {
         match &self {
@@ -110,28 +286,49 @@ impl SortDirection {
     }
 }
 
-pub fn sort_directory(request: &mut Request) -> Response<Cursor<Vec<u8>>> {
-    let current_url = find_header(request, "HX-Current-Url").unwrap();
-    let mut query: DirectoryQuery = extract_query(current_url.value.as_str()).unwrap();
-    let sort: CreateDirectorySortRequest = parse_form_data(request).unwrap();
+pub fn sort_directory(request: &mut Request) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let mut query = parse_current_query(request)?;
+    let sort: CreateDirectorySortRequest = parse_form_data(request)?;
+    find_column(&sort.column)?;
 
     query.sort_column = Some(sort.column);
+    query.page = None;
     query.sort_direction = Some(match sort.state {
         SortState::Unsorted => SortDirection::Ascending,
         SortState::Ascending => SortDirection::Descending,
         SortState::Descending => SortDirection::Ascending,
     });
 
-    empty_fragment()
+    Ok(empty_fragment()
         .with_header(Header::from_str("Content-Type: text/html").unwrap())
-        .with_header(
-            Header::from_str(&format!(
-                "HX-Push-Url: /?{}",
-                serde_qs::to_string(&query).unwrap()
-            ))
-            .unwrap(),
-        )
-        .with_header(Header::from_str("HX-Trigger-After-Settle: filter-directory").unwrap())
+        .with_header(push_url_header(request, &query)?)
+        .with_header(Header::from_str("HX-Trigger-After-Settle: filter-directory").unwrap()))
+}
+
+pub fn select_directory_year(request: &mut Request) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let mut query = parse_current_query(request)?;
+    let selection: SelectDirectoryYearRequest = parse_form_data(request)?;
+
+    query.year = selection.year.parse().ok();
+    query.page = None;
+
+    Ok(empty_fragment()
+        .with_header(Header::from_str("Content-Type: text/html").unwrap())
+        .with_header(push_url_header(request, &query)?)
+        .with_header(Header::from_str("HX-Trigger-After-Settle: filter-directory").unwrap()))
+}
+
+pub fn search_directory(request: &mut Request) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let mut query = parse_current_query(request)?;
+    let search: SearchDirectoryRequest = parse_form_data(request)?;
+
+    query.search = Some(search.search.trim().to_string()).filter(|search| !search.is_empty());
+    query.page = None;
+
+    Ok(empty_fragment()
+        .with_header(Header::from_str("Content-Type: text/html").unwrap())
+        .with_header(push_url_header(request, &query)?)
+        .with_header(Header::from_str("HX-Trigger-After-Settle: filter-directory").unwrap()))
 }
 
 pub fn fetch_columns() -> Vec<Column> {
@@ -139,199 +336,734 @@ pub fn fetch_columns() -> Vec<Column> {
         Column {
             name: String::from("Id"),
             formatted_name: String::from("Id"),
+            kind: ColumnKind::Text,
         },
         Column {
             name: String::from("Name"),
             formatted_name: String::from("Name"),
+            kind: ColumnKind::Text,
         },
         Column {
             name: String::from("Email"),
             formatted_name: String::from("Email"),
+            kind: ColumnKind::Text,
         },
         Column {
             name: String::from("Department"),
             formatted_name: String::from("Department"),
+            kind: ColumnKind::Text,
         },
         Column {
             name: String::from("Building"),
             formatted_name: String::from("Building"),
+            kind: ColumnKind::Text,
         },
         Column {
             name: String::from("Room"),
             formatted_name: String::from("Room"),
+            kind: ColumnKind::Text,
         },
         Column {
             name: String::from("AmountUsd"),
             formatted_name: String::from("Yearly Compensation"),
+            kind: ColumnKind::Currency,
         },
         Column {
             name: String::from("Year"),
             formatted_name: String::from("Year"),
+            kind: ColumnKind::Number,
         },
     ]
 }
 
-pub fn build_directory_filter_menu() -> Response<Cursor<Vec<u8>>> {
-    Response::from_string(
+// Renders the filter menu with the operators that fit the selected column's type
+pub fn build_directory_filter_menu(request: &Request) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let menu_query: DirectoryFilterMenuQuery = extract_query(request.url()).map_err(|error| {
+        Status::InvalidArgument(anyhow!("Failed to parse filter menu query: {}", error))
+    })?;
+    let column = find_column(menu_query.column.as_deref().unwrap_or("Id"))?;
+    let has_filters = parse_current_query(request)
+        .is_ok_and(|query| query.filters.is_some_and(|filters| !filters.is_empty()));
+
+    Ok(Response::from_string(
         DirectoryFilterMenu {
             columns: fetch_columns(),
+            selected_column: column.name,
+            operators: FilterOperator::for_kind(column.kind),
+            has_upper_value: column.kind != ColumnKind::Text,
+            has_filters,
         }
         .to_string(),
     )
-    .with_header(Header::from_str("Content-Type: text/html").unwrap())
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
 }
 
-pub fn delete_directory_filter(request: &mut Request) -> Response<Cursor<Vec<u8>>> {
-    let current_url = find_header(request, "HX-Current-Url").unwrap();
-    let mut query: DirectoryQuery = extract_query(current_url.value.as_str()).unwrap();
-    let filter: CreateDirectoryFilterRequest = parse_form_data(request).unwrap();
+pub fn delete_directory_filter(request: &mut Request) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let mut query = parse_current_query(request)?;
+    let filter = parse_form_data::<Filter>(request)?.normalize();
 
     if let Some(filters) = query.filters.as_mut() {
         if let Some(index) = filters
             .iter()
-            .position(|query_filter| query_filter == &format!("{}={}", filter.column, filter.value))
+            .position(|query_filter| query_filter == &filter)
         {
             filters.remove(index);
         }
     }
 
-    empty_fragment()
+    query.page = None;
+
+    Ok(empty_fragment()
         .with_header(Header::from_str("HX-Trigger-After-Settle: filter-directory").unwrap())
-        .with_header(
-            Header::from_str(&format!(
-                "HX-Push-Url: /?{}",
-                serde_qs::to_string(&query).unwrap()
-            ))
-            .unwrap(),
-        )
+        .with_header(push_url_header(request, &query)?))
 }
 
-pub fn create_directory_filter(request: &mut Request) -> Response<Cursor<Vec<u8>>> {
-    let filter: CreateDirectoryFilterRequest = parse_form_data(request).unwrap();
-    let current_url = find_header(&request, "HX-Current-Url").unwrap();
-    let mut query: DirectoryQuery = extract_query(current_url.value.as_str()).unwrap();
-    let serialized_filter = format!("{}={}", filter.column, filter.value);
-
-    if let Some(filters) = query.filters.as_mut() {
-        filters.push(serialized_filter);
-    } else {
-        query.filters = Some(vec![serialized_filter]);
+pub fn create_directory_filter(request: &mut Request) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let filter_request: CreateDirectoryFilterRequest = parse_form_data(request)?;
+    let mut query = parse_current_query(request)?;
+    let filters = query.filters.get_or_insert_with(Vec::new);
+    let last_group = filters.iter().map(|filter| filter.group).max();
+    let filter = Filter {
+        column: filter_request.column,
+        operator: filter_request.operator,
+        value: filter_request.value,
+        upper_value: filter_request.upper_value,
+        group: match (last_group, filter_request.join) {
+            (None, _) => 0,
+            (Some(group), Some(FilterJoin::Or)) => group + 1,
+            (Some(group), _) => group,
+        },
     }
+    .normalize();
+    filter.to_sql(&fetch_columns(), &mut vec![])?;
 
-    Response::from_string(
-        DirectoryFilter {
-            column: filter.column.clone(),
-            value: filter.value.clone(),
-        }
-        .to_string(),
-    )
-    .with_header(Header::from_str("Content-Type: text/html").unwrap())
-    .with_header(
-        Header::from_str(&format!(
-            "HX-Push-Url: /?{}",
-            serde_qs::to_string(&query).unwrap()
+    let chip = build_filter_chip(&filter, filters.last())?;
+    filters.push(filter);
+    query.page = None;
+
+    Ok(Response::from_string(chip.to_string())
+        .with_header(Header::from_str("Content-Type: text/html").unwrap())
+        .with_header(push_url_header(request, &query)?)
+        .with_header(Header::from_str("HX-Trigger-After-Settle: filter-directory").unwrap())
+        .with_header(
+            Header::from_str("HX-Trigger-After-Settle: close-directory-filter-menu").unwrap(),
         ))
-        .unwrap(),
-    )
-    .with_header(Header::from_str("HX-Trigger-After-Settle: filter-directory").unwrap())
-    .with_header(Header::from_str("HX-Trigger-After-Settle: close-directory-filter-menu").unwrap())
 }
 
-pub fn build_directory(request: &Request, context: &Arc<ServerState>) -> Response<Cursor<Vec<u8>>> {
-    let connection = context.connection_pool.get().unwrap();
-    let url = find_header(request, "HX-Current-Url").unwrap();
-    let query: DirectoryQuery = extract_query(url.value.as_str()).unwrap();
+fn build_filter_chip(
+    filter: &Filter,
+    previous: Option<&Filter>,
+) -> Result<DirectoryFilter, Status> {
+    let column = find_column(&filter.column)?;
+
+    Ok(DirectoryFilter {
+        filter: filter.clone(),
+        join: match previous {
+            None => String::new(),
+            Some(previous) if previous.group == filter.group => String::from("and"),
+            Some(_) => String::from("or"),
+        },
+        formatted_column: column.formatted_name,
+        operator: filter.operator.label().to_string(),
+    })
+}
+
+pub fn build_directory(
+    request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let connection = context.connection_pool.get()?;
+    let query = parse_current_query(request)?;
+    let page = build_directory_page(&query, &connection, false)?;
 
-    Response::from_string(
+    Ok(Response::from_string(
         Directory {
             headings: build_headings(&query, &fetch_columns()),
-            rows: build_rows(prepare_directory_statement(&query, &connection))
-                .into_iter()
-                .collect(),
+            page,
+            export_query: build_export_query(&query)?,
         }
         .to_string(),
     )
-    .with_header(Header::from_str("Content-Type: text/html").unwrap())
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
 }
 
-fn prepare_directory_statement<'a>(
-    query: &DirectoryQuery,
-    connection: &'a Connection,
-) -> Statement<'a> {
-    let condition: String = query
-        .filters
-        .as_ref()
-        .map(|filter| {
-            filter
-                .iter()
-                .map(|filter| {
-                    let mut parts = filter.split("=");
-                    let column = parts.next().unwrap();
-                    let value = parts.next().unwrap();
-
-                    format!("{} = '{}'", column, value)
-                })
-                .collect::<Vec<String>>()
-                .join(" OR ")
+// Swaps in the rows of another page, replacing the page controls out of band so the
+// headings and the rest of the directory stay in place
+pub fn page_directory(
+    request: &mut Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let connection = context.connection_pool.get()?;
+    let mut query = parse_current_query(request)?;
+    let page_request: PageDirectoryRequest = parse_form_data(request)?;
+    query.page = Some(page_request.page);
+
+    let page = build_directory_page(&query, &connection, true)?;
+    query.page = Some(page.pagination.page);
+
+    Ok(Response::from_string(page.to_string())
+        .with_header(Header::from_str("Content-Type: text/html").unwrap())
+        .with_header(push_url_header(request, &query)?))
+}
+
+// Fetches a page of the students matching the directory query in the url, one per student
+// whatever their salaries, along with their college id
+pub fn fetch_directory_students(
+    url: &str,
+    page_size: usize,
+    connection: &Connection,
+) -> Result<(Vec<(GraduateStudent, String)>, Pagination), Status> {
+    let query = parse_directory_query(url)?;
+    let (source, mut parameters) = build_student_source(&query)?;
+    let total = connection
+        .query_row(
+            &format!("SELECT COUNT(*) {}", source),
+            params_from_iter(&parameters),
+            |row| row.get(0),
+        )
+        .map_err(|error| {
+            Status::Internal(Error::from(error).context("Failed to count directory students"))
+        })?;
+    let pagination = Pagination::new(total, query.page, page_size);
+    // A salary column sorts each student by their latest salary, and Id breaks ties
+    let sort = if query.sort_column.is_none() && has_search(&query) {
+        String::from("ORDER BY SearchRank ASC, Id ASC")
+    } else {
+        let sort_column = find_column(query.sort_column.as_deref().unwrap_or("Id"))?.name;
+        format!(
+            "ORDER BY {} {}, Id ASC",
+            if SALARY_COLUMNS.contains(&sort_column.as_str()) {
+                format!(
+                    "(SELECT {} FROM Salaries
+                        WHERE Salaries.StudentId = Students.Id
+                        ORDER BY Year DESC
+                        LIMIT 1)",
+                    sort_column
+                )
+            } else {
+                sort_column
+            },
+            query
+                .sort_direction
+                .as_ref()
+                .unwrap_or(&SortDirection::Ascending)
+                .to_sql()
+        )
+    };
+    parameters.push(Value::Integer(pagination.page_size as i64));
+    parameters.push(Value::Integer(pagination.offset() as i64));
+    let mut statement = connection
+        .prepare(&format!(
+            "SELECT Id, Department, Email, Name, CollegeId, Building, Room
+                {} {} LIMIT ?{} OFFSET ?{}",
+            source,
+            sort,
+            parameters.len() - 1,
+            parameters.len()
+        ))
+        .map_err(|error| {
+            Status::Internal(Error::from(error).context("Failed to prepare directory students"))
+        })?;
+    let students = statement
+        .query_map(params_from_iter(parameters), |row| {
+            Ok((read_graduate_student(row)?, row.get("CollegeId")?))
         })
-        .unwrap_or(String::new());
-    let sort = format!(
-        "ORDER BY {} {}",
-        query.sort_column.as_ref().unwrap_or(&String::from("Id")),
-        query
-            .sort_direction
-            .as_ref()
-            .unwrap_or(&SortDirection::Ascending)
-            .to_sql()
-    );
+        .and_then(|rows| rows.collect::<Result<Vec<(GraduateStudent, String)>, rusqlite::Error>>())
+        .map_err(|error| {
+            Status::Internal(Error::from(error).context("Failed to read directory students"))
+        })?;
+
+    Ok((students, pagination))
+}
+
+// Streams every row matching the directory query, across all pages, as CSV or JSON
+pub fn export_directory(
+    request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Box<dyn Read + Send>>, Status> {
+    let query = parse_directory_query(request.url())?;
+    let format = extract_query::<ExportDirectoryQuery>(request.url())
+        .map_err(|error| {
+            Status::InvalidArgument(anyhow!("Failed to parse export format: {}", error))
+        })?
+        .format
+        .unwrap_or(ExportFormat::Csv);
+    let (reader, writer) = pipe().map_err(|error| {
+        Status::Internal(Error::from(error).context("Failed to open export pipe"))
+    })?;
+    let connection = context.connection_pool.get()?;
+    let (started_sender, started_receiver) = channel();
+
+    thread::spawn(move || {
+        if let Err(error) = run_export(&query, format, &connection, writer, started_sender) {
+            eprintln!("Directory export stopped: {}", error);
+        }
+    });
+
+    // The status is committed with the response, so wait until the query has run its first step
+    started_receiver
+        .recv()
+        .map_err(|_| Status::Internal(anyhow!("Directory export stopped before it started")))??;
 
-    if condition.is_empty() {
-        return connection
-            .prepare(&format!(
-                "SELECT Id, Department, Email, Name, Year, AmountUsd, CollegeId, Building, Room
-                 FROM Students 
-                 JOIN Salaries 
-                 ON Students.Id = Salaries.StudentId 
-                 LEFT JOIN Offices
-                 ON Students.Id = Offices.StudentId 
-                 {}",
-                sort
+    let (content_type, extension) = match format {
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        ExportFormat::Json => ("application/json", "json"),
+    };
+
+    // Without a length tiny_http sends the rows chunked as the export thread writes them
+    Ok(Response::new(
+        StatusCode::from(200),
+        vec![
+            Header::from_str(&format!("Content-Type: {}", content_type)).unwrap(),
+            Header::from_str(&format!(
+                "Content-Disposition: attachment; filename=\"directory.{}\"",
+                extension
             ))
-            .unwrap();
+            .unwrap(),
+        ],
+        reader,
+        None,
+        None,
+    )
+    .boxed())
+}
+
+// Prepares the export query and reads its first row before reporting the export as started,
+// so a query that fails is answered with its error instead of a truncated 200
+fn run_export(
+    query: &DirectoryQuery,
+    format: ExportFormat,
+    connection: &Connection,
+    writer: impl Write,
+    started: Sender<Result<(), Status>>,
+) -> Result<(), Error> {
+    let (mut statement, parameters) = match prepare_directory_statement(query, None, connection) {
+        Ok(prepared) => prepared,
+        Err(status) => {
+            let _ = started.send(Err(status));
+            return Ok(());
+        }
+    };
+    let mut rows = match statement.query(params_from_iter(parameters)) {
+        Ok(rows) => rows,
+        Err(error) => {
+            let _ = started.send(Err(Status::from(error)));
+            return Ok(());
+        }
+    };
+    let first_row = match read_next_export_row(&mut rows) {
+        Ok(first_row) => first_row,
+        Err(error) => {
+            let _ = started.send(Err(Status::from(error)));
+            return Ok(());
+        }
+    };
+    let _ = started.send(Ok(()));
+
+    match format {
+        ExportFormat::Csv => write_csv_export(first_row, rows, writer),
+        ExportFormat::Json => write_json_export(first_row, rows, writer),
+    }
+}
+
+fn write_csv_export(
+    first_row: Option<DirectoryExportRow>,
+    mut rows: Rows,
+    writer: impl Write,
+) -> Result<(), Error> {
+    let mut csv_writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(writer);
+    csv_writer.write_record(EXPORT_HEADERS)?;
+    let mut row = first_row;
+
+    while let Some(export_row) = row {
+        csv_writer.serialize(export_row.escape_formulas())?;
+        row = read_next_export_row(&mut rows)?;
     }
 
+    csv_writer.flush()?;
+
+    Ok(())
+}
+
+fn write_json_export(
+    first_row: Option<DirectoryExportRow>,
+    mut rows: Rows,
+    writer: impl Write,
+) -> Result<(), Error> {
+    let mut writer = BufWriter::new(writer);
+    let mut separator = "";
+    let mut row = first_row;
+    writer.write_all(b"[")?;
+
+    while let Some(export_row) = row {
+        writer.write_all(separator.as_bytes())?;
+        serde_json::to_writer(&mut writer, &export_row)?;
+        separator = ",";
+        row = read_next_export_row(&mut rows)?;
+    }
+
+    writer.write_all(b"]")?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn read_next_export_row(rows: &mut Rows) -> Result<Option<DirectoryExportRow>, rusqlite::Error> {
+    rows.next()?.map(read_export_row).transpose()
+}
+
+fn read_export_row(row: &Row) -> Result<DirectoryExportRow, rusqlite::Error> {
+    let compensation_cents: usize = row.get("AmountUsd")?;
+
+    Ok(DirectoryExportRow {
+        id: row.get("Id")?,
+        name: row.get("Name")?,
+        email: row.get("Email")?,
+        department: row.get("Department")?,
+        building: row
+            .get::<_, Option<String>>("Building")?
+            .unwrap_or_default(),
+        room: row.get::<_, Option<String>>("Room")?.unwrap_or_default(),
+        year: row.get("Year")?,
+        compensation_cents,
+        compensation_usd: format_usd(compensation_cents),
+    })
+}
+
+// The export covers every page, so the links carry the query without its page
+fn build_export_query(query: &DirectoryQuery) -> Result<String, Status> {
+    serialize_query(&DirectoryQuery {
+        page: None,
+        ..query.clone()
+    })
+}
+
+// Reads the directory query from the page url htmx sends with every request. A college
+// page scopes its directory to the college in its path
+fn parse_current_query(request: &Request) -> Result<DirectoryQuery, Status> {
+    let url = require_header(request, "HX-Current-Url")?.value.as_str();
+    let mut query = parse_directory_query(url)?;
+
+    if let Some(college_id) = find_college_id(find_path(url)) {
+        query.college_id = Some(college_id.to_string());
+    }
+
+    Ok(query)
+}
+
+// Builds the directory embedded in a college page, scoped to the college
+pub fn build_college_directory(
+    url: &str,
+    college_id: &str,
+    connection: &Connection,
+) -> Result<Directory, Status> {
+    let mut query = parse_directory_query(url)?;
+    query.college_id = Some(college_id.to_string());
+
+    Ok(Directory {
+        headings: build_headings(&query, &fetch_columns()),
+        page: build_directory_page(&query, connection, false)?,
+        export_query: build_export_query(&query)?,
+    })
+}
+
+// Links to the directory filtered to one department of a college
+pub fn build_department_directory_url(
+    college_id: &str,
+    department: &str,
+) -> Result<String, Status> {
+    let query = DirectoryQuery {
+        filters: Some(vec![Filter {
+            column: String::from("Department"),
+            operator: FilterOperator::Equals,
+            value: department.to_string(),
+            upper_value: None,
+            group: 0,
+        }]),
+        college_id: Some(college_id.to_string()),
+        ..DirectoryQuery::default()
+    };
+
+    Ok(format!("/?{}", serialize_query(&query)?))
+}
+
+pub fn find_college_id(path: &str) -> Option<&str> {
+    path.strip_prefix("/college/")
+        .filter(|college_id| !college_id.is_empty() && !college_id.contains('/'))
+}
+
+// Strips the origin and query from a url such as "http://localhost:8080/college/3?page=2"
+fn find_path(url: &str) -> &str {
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |index| &rest[index..]),
+        None => url,
+    };
+
+    path.split('?').next().unwrap_or(path)
+}
+
+fn serialize_query(query: &DirectoryQuery) -> Result<String, Status> {
+    serde_qs::to_string(query).map_err(|error| {
+        Status::Internal(anyhow!("Failed to serialize directory query: {}", error))
+    })
+}
+
+// Pushes the query onto the current page, leaving out a college its path already names
+fn push_url_header(request: &Request, query: &DirectoryQuery) -> Result<Header, Status> {
+    let path = find_path(require_header(request, "HX-Current-Url")?.value.as_str());
+    let query = match find_college_id(path) {
+        Some(_) => serialize_query(&DirectoryQuery {
+            college_id: None,
+            ..query.clone()
+        })?,
+        None => serialize_query(query)?,
+    };
+
+    Header::from_str(&format!("HX-Push-Url: {}?{}", path, query))
+        .map_err(|_| Status::Internal(anyhow!("Invalid push url for '{}'", query)))
+}
+
+// Checks the sort column and every filter against the directory columns so only known
+// column names ever reach the SQL
+fn parse_directory_query(url: &str) -> Result<DirectoryQuery, Status> {
+    let query: DirectoryQuery = extract_query(url).map_err(|error| {
+        Status::InvalidArgument(anyhow!("Failed to parse directory query: {}", error))
+    })?;
+
+    if let Some(sort_column) = &query.sort_column {
+        find_column(sort_column)?;
+    }
+
+    let columns = fetch_columns();
+
+    for filter in query.filters.iter().flatten() {
+        filter.to_sql(&columns, &mut vec![])?;
+    }
+
+    Ok(query)
+}
+
+fn find_column(name: &str) -> Result<Column, Status> {
+    fetch_columns()
+        .into_iter()
+        .find(|column| column.name == name)
+        .ok_or_else(|| Status::InvalidArgument(anyhow!("Unknown directory column '{}'", name)))
+}
+
+// Builds the tables and WHERE clause shared by the directory rows and their count. A
+// search joins the matching students along with their rank
+fn build_directory_source(query: &DirectoryQuery) -> Result<(String, Vec<Value>), Status> {
+    let mut parameters = vec![];
+    let mut conditions = vec![];
+    let mut tables = String::from(DIRECTORY_TABLES);
+
+    push_search_join(query, &mut tables, &mut parameters);
+
+    if let Some(filter_condition) = build_filter_condition(
+        query.filters.as_deref().unwrap_or_default(),
+        &fetch_columns(),
+        &mut parameters,
+    )? {
+        conditions.push(format!("({})", filter_condition));
+    }
+
+    if let Some(year) = query.year {
+        parameters.push(Value::Integer(year as i64));
+        conditions.push(format!("Year = ?{}", parameters.len()));
+    }
+
+    if let Some(college_id) = &query.college_id {
+        parameters.push(Value::Text(college_id.clone()));
+        conditions.push(format!("CollegeId = ?{}", parameters.len()));
+    }
+
+    if !conditions.is_empty() {
+        tables.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+    }
+
+    Ok((tables, parameters))
+}
+
+// Builds the tables and WHERE clause of the students matching the directory query. The
+// salary filters and year hold when any one salary of the student matches them
+fn build_student_source(query: &DirectoryQuery) -> Result<(String, Vec<Value>), Status> {
+    let mut parameters = vec![];
+    let mut conditions = vec![];
+    let mut salary_conditions = vec![];
+    let mut tables = String::from(STUDENT_TABLES);
+
+    push_search_join(query, &mut tables, &mut parameters);
+
+    let filters = query.filters.as_deref().unwrap_or_default();
+    if let Some(filter_condition) =
+        build_filter_condition(filters, &fetch_columns(), &mut parameters)?
+    {
+        // Filters are joined by AND or OR, so salary filters move the whole condition
+        if filters
+            .iter()
+            .any(|filter| SALARY_COLUMNS.contains(&filter.column.as_str()))
+        {
+            salary_conditions.push(format!("({})", filter_condition));
+        } else {
+            conditions.push(format!("({})", filter_condition));
+        }
+    }
+
+    if let Some(year) = query.year {
+        parameters.push(Value::Integer(year as i64));
+        salary_conditions.push(format!("Year = ?{}", parameters.len()));
+    }
+
+    if !salary_conditions.is_empty() {
+        conditions.push(format!(
+            "EXISTS (SELECT 1 FROM Salaries WHERE Salaries.StudentId = Students.Id AND {})",
+            salary_conditions.join(" AND ")
+        ));
+    }
+
+    if let Some(college_id) = &query.college_id {
+        parameters.push(Value::Text(college_id.clone()));
+        conditions.push(format!("CollegeId = ?{}", parameters.len()));
+    }
+
+    if !conditions.is_empty() {
+        tables.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+    }
+
+    Ok((tables, parameters))
+}
+
+// Joins the students matching the search along with their rank
+fn push_search_join(query: &DirectoryQuery, tables: &mut String, parameters: &mut Vec<Value>) {
+    if let Some(search_match) = query.search.as_deref().and_then(build_search_match) {
+        parameters.push(Value::Text(search_match));
+        tables.push_str(&format!(
+            "
+            JOIN (
+                SELECT StudentId AS SearchStudentId, {} AS SearchRank
+                FROM StudentSearch
+                WHERE StudentSearch MATCH ?{}
+            )
+            ON Students.Id = SearchStudentId",
+            SEARCH_RANK,
+            parameters.len()
+        ));
+    }
+}
+
+fn count_directory_rows(query: &DirectoryQuery, connection: &Connection) -> Result<usize, Status> {
+    let (source, parameters) = build_directory_source(query)?;
+
     connection
+        .query_row(
+            &format!("SELECT COUNT(*) {}", source),
+            params_from_iter(parameters),
+            |row| row.get(0),
+        )
+        .map_err(|error| {
+            Status::Internal(Error::from(error).context("Failed to count directory rows"))
+        })
+}
+
+// Selects the rows of the directory query, limited to a page when one is given
+fn prepare_directory_statement<'a>(
+    query: &DirectoryQuery,
+    pagination: Option<&Pagination>,
+    connection: &'a Connection,
+) -> Result<(Statement<'a>, Vec<Value>), Status> {
+    let (source, mut parameters) = build_directory_source(query)?;
+    let is_ranked = query.sort_column.is_none() && has_search(query);
+    // Id and Year break ties so rows with equal sort values keep their page
+    let sort = if is_ranked {
+        String::from("ORDER BY SearchRank ASC, Id ASC, Year ASC")
+    } else {
+        format!(
+            "ORDER BY {} {}, Id ASC, Year ASC",
+            find_column(query.sort_column.as_deref().unwrap_or("Id"))?.name,
+            query
+                .sort_direction
+                .as_ref()
+                .unwrap_or(&SortDirection::Ascending)
+                .to_sql()
+        )
+    };
+    let limit = match pagination {
+        Some(pagination) => {
+            parameters.push(Value::Integer(pagination.page_size as i64));
+            parameters.push(Value::Integer(pagination.offset() as i64));
+            format!(
+                "LIMIT ?{} OFFSET ?{}",
+                parameters.len() - 1,
+                parameters.len()
+            )
+        }
+        None => String::new(),
+    };
+    let statement = connection
         .prepare(&format!(
-            "SELECT Id, Department, Email, Name, Year, AmountUsd, CollegeId, Building, Room
-                 FROM Students 
-                 JOIN Salaries 
-                 ON Students.Id = Salaries.StudentId 
-                 LEFT JOIN Offices
-                 ON Students.Id = Offices.StudentId
-                 WHERE {} {}",
-            condition, sort
+            "SELECT Id, Department, Email, Name, Year, AmountUsd, CollegeId, Building, Room,
+                    Salaries.StudentId, SalaryName, SalaryDepartment, Confidence, Method
+                 {} {} {}",
+            source, sort, limit
         ))
-        .unwrap()
+        .map_err(|error| {
+            Status::Internal(Error::from(error).context("Failed to prepare directory query"))
+        })?;
+
+    Ok((statement, parameters))
 }
 
-fn build_rows(mut statement: Statement) -> Vec<StudentDirectoryRow> {
-    let mut query = statement.query([]).unwrap();
+fn has_search(query: &DirectoryQuery) -> bool {
+    query
+        .search
+        .as_deref()
+        .and_then(build_search_match)
+        .is_some()
+}
+
+fn build_directory_page(
+    query: &DirectoryQuery,
+    connection: &Connection,
+    swap_pagination: bool,
+) -> Result<DirectoryPage, Status> {
+    let pagination = Pagination::new(
+        count_directory_rows(query, connection)?,
+        query.page,
+        DIRECTORY_PAGE_SIZE,
+    );
+    let (statement, parameters) =
+        prepare_directory_statement(query, Some(&pagination), connection)?;
+
+    Ok(DirectoryPage {
+        rows: build_rows(statement, parameters)?,
+        pagination,
+        swap_pagination,
+    })
+}
+
+fn build_rows(
+    mut statement: Statement,
+    parameters: Vec<Value>,
+) -> Result<Vec<StudentDirectoryRow>, Status> {
+    let mut query = statement.query(params_from_iter(parameters))?;
     let mut directory = Vec::new();
 
-    while let Ok(Some(row)) = query.next() {
-        let name: String = row.get("Name").unwrap();
-        let year: usize = row.get("Year").unwrap();
-        let yearly_compensation: usize = row.get("AmountUsd").unwrap();
+    while let Some(row) = query.next()? {
+        let name: String = row.get("Name")?;
+        let year: usize = row.get("Year")?;
+        let yearly_compensation: usize = row.get("AmountUsd")?;
         let dollars = yearly_compensation / 100;
         let cents = yearly_compensation % 100;
         let mut compensation_buffer = Buffer::default();
         compensation_buffer.write_formatted(&dollars, &Locale::en);
 
         directory.push(StudentDirectoryRow {
-            id: row.get("Id").unwrap(),
-            college_id: row.get("CollegeId").unwrap(),
-            department: row.get("Department").unwrap(),
-            email: row.get("Email").unwrap(),
+            id: row.get("Id")?,
+            college_id: row.get("CollegeId")?,
+            department: row.get("Department")?,
+            email: row.get("Email")?,
             name: name
                 .split(", ")
                 .map(|part| part.to_string())
@@ -346,52 +1078,63 @@ fn build_rows(mut statement: Statement) -> Vec<StudentDirectoryRow> {
         });
     }
 
-    directory
-}
-
-pub fn list_students(request: &Request, context: &Arc<ServerState>) -> Response<Cursor<Vec<u8>>> {
-    let connection = context.connection_pool.get().unwrap();
-    let query: DirectoryQuery = extract_query(request.url()).unwrap();
-    let filters: Vec<DirectoryFilter> = query
-        .filters
-        .as_ref()
-        .map(|filter| {
-            filter
-                .iter()
-                .map(|filter| {
-                    let mut parts = filter.split("=");
-                    let column = parts.next().unwrap();
-                    let value = parts.next().unwrap();
-
-                    DirectoryFilter {
-                        column: column.to_string(),
-                        value: value.to_string(),
-                    }
-                })
-                .collect()
+    Ok(directory)
+}
+
+pub fn list_students(
+    request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let connection = context.connection_pool.get()?;
+    let query = parse_directory_query(request.url())?;
+    let query_filters = query.filters.as_deref().unwrap_or_default();
+    let filters: Vec<DirectoryFilter> = query_filters
+        .iter()
+        .enumerate()
+        .map(|(index, filter)| {
+            build_filter_chip(
+                filter,
+                index
+                    .checked_sub(1)
+                    .map(|previous| &query_filters[previous]),
+            )
         })
-        .unwrap_or(vec![]);
+        .collect::<Result<Vec<DirectoryFilter>, Status>>()?;
+    let page = build_directory_page(&query, &connection, false)?;
 
-    Response::from_string(
+    Ok(Response::from_string(
         ListStudents {
             directory: Directory {
                 headings: build_headings(&query, &fetch_columns()),
-                rows: build_rows(prepare_directory_statement(&query, &connection)),
+                page,
+                export_query: build_export_query(&query)?,
             },
             filters,
+            years: fetch_salary_years(&connection)?
+                .into_iter()
+                .map(|year| YearOption {
+                    year,
+                    selected: query.year == Some(year),
+                })
+                .collect(),
+            search: query.search.clone().unwrap_or_default(),
         }
         .to_string(),
     )
-    .with_header(Header::from_str("Content-Type: text/html").unwrap())
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
 }
 
-fn build_headings(query: &DirectoryQuery, columns: &Vec<Column>) -> Vec<DirectoryHeading> {
+fn build_headings(query: &DirectoryQuery, columns: &[Column]) -> Vec<DirectoryHeading> {
     columns
         .iter()
         .map(|column| {
             let state = if let Some(sort_column) = query.sort_column.as_ref() {
                 if &column.name == sort_column {
-                    match &query.sort_direction.as_ref().unwrap() {
+                    match query
+                        .sort_direction
+                        .as_ref()
+                        .unwrap_or(&SortDirection::Ascending)
+                    {
                         SortDirection::Descending => SortState::Descending,
                         SortDirection::Ascending => SortState::Ascending,
                     }
@@ -399,7 +1142,8 @@ fn build_headings(query: &DirectoryQuery, columns: &Vec<Column>) -> Vec<Director
                     SortState::Unsorted
                 }
             } else {
-                if column.name == "Id" {
+                // Searches without a sort column are ordered by rank
+                if column.name == "Id" && !has_search(query) {
                     SortState::Ascending
                 } else {
                     SortState::Unsorted
@@ -2,18 +2,91 @@ use std::fmt::{Display, Formatter};
 
 use anyhow::Error;
 
+// Error raised for a single scraped row that keeps the raw html or json of the row
+#[derive(Debug)]
+pub struct RowError {
+    pub message: String,
+    pub snippet: String,
+}
+
 #[derive(Debug)]
 pub enum Status {
     NotFound(Error),
     InvalidArgument(Error),
+    Unauthenticated(Error),
+    PermissionDenied(Error),
     Internal(Error),
 }
 
+impl Status {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Status::NotFound(_) => "NotFound",
+            Status::InvalidArgument(_) => "InvalidArgument",
+            Status::Unauthenticated(_) => "Unauthenticated",
+            Status::PermissionDenied(_) => "PermissionDenied",
+            Status::Internal(_) => "Internal",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        self.error().to_string()
+    }
+
+    pub fn snippet(&self) -> Option<&str> {
+        self.error()
+            .downcast_ref::<RowError>()
+            .map(|row_error| row_error.snippet.as_str())
+    }
+
+    fn error(&self) -> &Error {
+        match self {
+            Status::NotFound(error)
+            | Status::InvalidArgument(error)
+            | Status::Unauthenticated(error)
+            | Status::PermissionDenied(error)
+            | Status::Internal(error) => error,
+        }
+    }
+}
+
+impl RowError {
+    pub fn new(message: &str, snippet: String) -> RowError {
+        RowError {
+            message: String::from(message),
+            snippet,
+        }
+    }
+}
+
+impl Display for RowError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for RowError {}
+
+// Database and connection pool failures are never caused by the request
+impl From<rusqlite::Error> for Status {
+    fn from(error: rusqlite::Error) -> Status {
+        Status::Internal(Error::from(error).context("Database query failed"))
+    }
+}
+
+impl From<r2d2::Error> for Status {
+    fn from(error: r2d2::Error) -> Status {
+        Status::Internal(Error::from(error).context("Failed to get a database connection"))
+    }
+}
+
 impl Display for Status {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Status::NotFound(error) => write!(f, "NotFound: {}", error),
             Status::InvalidArgument(error) => write!(f, "InvalidArgument: {}", error),
+            Status::Unauthenticated(error) => write!(f, "Unauthenticated: {}", error),
+            Status::PermissionDenied(error) => write!(f, "PermissionDenied: {}", error),
             Status::Internal(error) => write!(f, "Internal: {}", error),
         }
     }
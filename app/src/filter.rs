@@ -0,0 +1,250 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::{directory::Column, error::Status, salary::parse_usd_cents};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Text,
+    Number,
+    Currency,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOperator {
+    Contains,
+    StartsWith,
+    Equals,
+    NotEquals,
+    LessThan,
+    GreaterThan,
+    Between,
+    IsEmpty,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterJoin {
+    And,
+    Or,
+}
+
+// A single directory filter as encoded in the url. Filters sharing a group are combined
+// with AND and the groups are combined with OR
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    pub column: String,
+    pub operator: FilterOperator,
+    #[serde(default)]
+    pub value: String,
+    pub upper_value: Option<String>,
+    #[serde(default)]
+    pub group: usize,
+}
+
+impl FilterOperator {
+    pub fn for_kind(kind: ColumnKind) -> Vec<FilterOperator> {
+        match kind {
+            ColumnKind::Text => vec![
+                FilterOperator::Contains,
+                FilterOperator::StartsWith,
+                FilterOperator::Equals,
+                FilterOperator::NotEquals,
+                FilterOperator::IsEmpty,
+            ],
+            ColumnKind::Number | ColumnKind::Currency => vec![
+                FilterOperator::Equals,
+                FilterOperator::NotEquals,
+                FilterOperator::LessThan,
+                FilterOperator::GreaterThan,
+                FilterOperator::Between,
+                FilterOperator::IsEmpty,
+            ],
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilterOperator::Contains => "Contains",
+            FilterOperator::StartsWith => "StartsWith",
+            FilterOperator::Equals => "Equals",
+            FilterOperator::NotEquals => "NotEquals",
+            FilterOperator::LessThan => "LessThan",
+            FilterOperator::GreaterThan => "GreaterThan",
+            FilterOperator::Between => "Between",
+            FilterOperator::IsEmpty => "IsEmpty",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FilterOperator::Contains => "contains",
+            FilterOperator::StartsWith => "starts with",
+            FilterOperator::Equals => "=",
+            FilterOperator::NotEquals => "!=",
+            FilterOperator::LessThan => "<",
+            FilterOperator::GreaterThan => ">",
+            FilterOperator::Between => "between",
+            FilterOperator::IsEmpty => "is empty",
+        }
+    }
+}
+
+impl Filter {
+    // Drops values the operator does not use so equal filters serialize the same way
+    pub fn normalize(mut self) -> Filter {
+        self.value = self.value.trim().to_string();
+        self.upper_value = self
+            .upper_value
+            .map(|upper_value| upper_value.trim().to_string())
+            .filter(|upper_value| !upper_value.is_empty());
+
+        match self.operator {
+            FilterOperator::IsEmpty => {
+                self.value = String::new();
+                self.upper_value = None;
+            }
+            FilterOperator::Between => (),
+            _ => self.upper_value = None,
+        }
+
+        self
+    }
+
+    // Builds the SQL condition for the filter, binding its values as parameters
+    pub fn to_sql(
+        &self,
+        columns: &[Column],
+        parameters: &mut Vec<Value>,
+    ) -> Result<String, Status> {
+        let column = columns
+            .iter()
+            .find(|column| column.name == self.column)
+            .ok_or_else(|| {
+                Status::InvalidArgument(anyhow!("Unknown directory column '{}'", self.column))
+            })?;
+
+        if !FilterOperator::for_kind(column.kind).contains(&self.operator) {
+            return Err(Status::InvalidArgument(anyhow!(
+                "{} can't be filtered with '{}'",
+                column.formatted_name,
+                self.operator.label()
+            )));
+        }
+
+        Ok(match self.operator {
+            FilterOperator::Contains => {
+                parameters.push(Value::Text(format!("%{}%", escape_like(&self.value))));
+                format!("{} LIKE ?{} ESCAPE '\\'", column.name, parameters.len())
+            }
+            FilterOperator::StartsWith => {
+                parameters.push(Value::Text(format!("{}%", escape_like(&self.value))));
+                format!("{} LIKE ?{} ESCAPE '\\'", column.name, parameters.len())
+            }
+            FilterOperator::Equals => format!(
+                "{} = {}",
+                column.name,
+                bind_value(column, &self.value, parameters)?
+            ),
+            FilterOperator::NotEquals => {
+                format!(
+                    "{} IS NOT {}",
+                    column.name,
+                    bind_value(column, &self.value, parameters)?
+                )
+            }
+            FilterOperator::LessThan => {
+                format!(
+                    "{} < {}",
+                    column.name,
+                    bind_value(column, &self.value, parameters)?
+                )
+            }
+            FilterOperator::GreaterThan => {
+                format!(
+                    "{} > {}",
+                    column.name,
+                    bind_value(column, &self.value, parameters)?
+                )
+            }
+            FilterOperator::Between => {
+                let Some(upper_value) = &self.upper_value else {
+                    return Err(Status::InvalidArgument(anyhow!(
+                        "Between filters on {} need an upper value",
+                        column.formatted_name
+                    )));
+                };
+                let lower = bind_value(column, &self.value, parameters)?;
+                let upper = bind_value(column, upper_value, parameters)?;
+
+                format!("{} BETWEEN {} AND {}", column.name, lower, upper)
+            }
+            FilterOperator::IsEmpty => format!("({0} IS NULL OR {0} = '')", column.name),
+        })
+    }
+}
+
+// Combines the filters into "(a AND b) OR (c)", returning None when there are no filters
+pub fn build_filter_condition(
+    filters: &[Filter],
+    columns: &[Column],
+    parameters: &mut Vec<Value>,
+) -> Result<Option<String>, Status> {
+    let mut groups: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+
+    for filter in filters {
+        groups
+            .entry(filter.group)
+            .or_default()
+            .push(filter.to_sql(columns, parameters)?);
+    }
+
+    if groups.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(
+        groups
+            .values()
+            .map(|conditions| format!("({})", conditions.join(" AND ")))
+            .collect::<Vec<String>>()
+            .join(" OR "),
+    ))
+}
+
+fn bind_value(column: &Column, value: &str, parameters: &mut Vec<Value>) -> Result<String, Status> {
+    parameters.push(parse_value(column, value)?);
+
+    Ok(format!("?{}", parameters.len()))
+}
+
+fn parse_value(column: &Column, value: &str) -> Result<Value, Status> {
+    match column.kind {
+        ColumnKind::Text => Ok(Value::Text(value.to_string())),
+        ColumnKind::Number => value.parse().map(Value::Integer).map_err(|_| {
+            Status::InvalidArgument(anyhow!(
+                "{} expects a number, got '{}'",
+                column.formatted_name,
+                value
+            ))
+        }),
+        ColumnKind::Currency => parse_usd_cents(value)
+            .map(|cents| Value::Integer(cents as i64))
+            .ok_or_else(|| {
+                Status::InvalidArgument(anyhow!(
+                    "{} expects a dollar amount, got '{}'",
+                    column.formatted_name,
+                    value
+                ))
+            }),
+    }
+}
+
+pub fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
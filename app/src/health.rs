@@ -9,7 +9,7 @@ use tokio::task::JoinSet;
 
 use crate::{
     college::{GraduateStudent, Office},
-    error::Status,
+    error::{RowError, Status},
     html::{scrape_html, ScrapperSelectors},
     parser::{HtmlRowParser, LastNameFirstParser},
     scraper::{PagedRequest, PagedResponse, StudentScraper},
@@ -146,19 +146,27 @@ impl StudentScraper<HealthScrapperRequest, HealthScrapperResponse> for HealthScr
         )?
         .iter()
         .map(|row| {
+            let row_error = |message| {
+                Status::NotFound(Error::from(RowError::new(
+                    message,
+                    row.element
+                        .map(|element| element.html())
+                        .unwrap_or_default(),
+                )))
+            };
             let Some(name_link) = &row.name_elements.first() else {
-                return Err(Status::NotFound(anyhow!("Name link element not found")));
+                return Err(row_error("Name link element not found"));
             };
             let Some(name_url) = name_link.attr("href") else {
-                return Err(Status::NotFound(anyhow!("Name url not found in href")));
+                return Err(row_error("Name url not found in href"));
             };
             let Some(department_element) = row.department_element else {
-                return Err(Status::NotFound(anyhow!("Department element not found")));
+                return Err(row_error("Department element not found"));
             };
             let names = parser.parse_names(&row.name_elements);
 
             if names.is_empty() {
-                return Err(Status::NotFound(anyhow!("No names found")));
+                return Err(row_error("No names found"));
             }
 
             Ok((
@@ -203,7 +211,10 @@ impl StudentScraper<HealthScrapperRequest, HealthScrapperResponse> for HealthScr
             {
                 Some(email_element) => {
                     let Some(email) = parser.parse_email(&Some(email_element)) else {
-                        students.push(Err(Status::InvalidArgument(anyhow!("Invalid email"))));
+                        students.push(Err(Status::InvalidArgument(Error::from(RowError::new(
+                            "Invalid email",
+                            email_element.html(),
+                        )))));
                         continue;
                     };
                     let Some(id) = email
@@ -212,14 +223,20 @@ impl StudentScraper<HealthScrapperRequest, HealthScrapperResponse> for HealthScr
                         .next()
                         .and_then(|id| Some(id.to_lowercase()))
                     else {
-                        students.push(Err(Status::InvalidArgument(anyhow!("Invalid id in email"))));
+                        students.push(Err(Status::InvalidArgument(Error::from(RowError::new(
+                            "Invalid id in email",
+                            email_element.html(),
+                        )))));
                         continue;
                     };
                     student.email = email;
                     student.id = id;
                     students.push(Ok(student));
                 }
-                None => students.push(Err(Status::NotFound(anyhow!("Email element not found")))),
+                None => students.push(Err(Status::NotFound(Error::from(RowError::new(
+                    "Email element not found",
+                    serde_json::to_string(&student).unwrap_or_default(),
+                ))))),
             };
         }
 
@@ -0,0 +1,216 @@
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use crate::{
+    college::{College, GraduateStudent},
+    error::Status,
+};
+
+struct StudentVersion {
+    version: usize,
+    name: String,
+    email: String,
+    department: String,
+    college_id: String,
+    absent_since: Option<u64>,
+}
+
+pub struct DepartmentChange {
+    pub name: String,
+    pub department: String,
+    pub change: String,
+    pub changed_at: String,
+}
+
+// Records a new history version for students whose details changed or who came back
+// after being absent, otherwise marks the latest version as seen in this run
+pub fn record_student_history(
+    run_id: &str,
+    seen_at: u64,
+    college: &College,
+    students: &[Result<GraduateStudent, Status>],
+    connection_pool: &Pool<SqliteConnectionManager>,
+) {
+    let mut connection = connection_pool.get().unwrap();
+    let transaction = connection.transaction().unwrap();
+
+    for student in students.iter().flatten() {
+        let name = student.names.join(" ");
+        let Some(latest) = fetch_latest_version(&transaction, &student.id) else {
+            insert_version(&transaction, run_id, seen_at, college, student, 1, "");
+            continue;
+        };
+        let mut changed_fields = vec![];
+
+        if latest.name != name {
+            changed_fields.push("Name");
+        }
+
+        if latest.email != student.email {
+            changed_fields.push("Email");
+        }
+
+        if latest.department != student.department {
+            changed_fields.push("Department");
+        }
+
+        if latest.college_id != college.id {
+            changed_fields.push("College");
+        }
+
+        if latest.absent_since.is_some() {
+            changed_fields.push("Returned");
+        }
+
+        if changed_fields.is_empty() {
+            transaction
+                .execute(
+                    "UPDATE StudentHistory SET LastSeenAt = ?1, LastSeenRunId = ?2
+                    WHERE StudentId = ?3 AND Version = ?4",
+                    params![seen_at, run_id, student.id, latest.version],
+                )
+                .unwrap();
+            continue;
+        }
+
+        insert_version(
+            &transaction,
+            run_id,
+            seen_at,
+            college,
+            student,
+            latest.version + 1,
+            &changed_fields.join(","),
+        );
+    }
+
+    transaction.commit().unwrap();
+}
+
+// Marks students of a fully scraped college that were not seen in this run as absent
+pub fn mark_absent_students(
+    run_id: &str,
+    absent_since: u64,
+    college: &College,
+    connection_pool: &Pool<SqliteConnectionManager>,
+) -> usize {
+    connection_pool
+        .get()
+        .unwrap()
+        .execute(
+            "UPDATE StudentHistory SET AbsentSince = ?1
+            WHERE CollegeId = ?2
+            AND LastSeenRunId != ?3
+            AND AbsentSince IS NULL
+            AND Version = (
+                SELECT MAX(Latest.Version) FROM StudentHistory AS Latest
+                WHERE Latest.StudentId = StudentHistory.StudentId
+            )",
+            params![absent_since, college.id, run_id],
+        )
+        .unwrap()
+}
+
+// Lists the most recent students who joined or left a department. Students found by the
+// first scrape of their college are left out since they were already there
+pub fn fetch_department_changes(
+    connection: &Connection,
+    limit: usize,
+) -> Result<Vec<DepartmentChange>, Status> {
+    let mut changes_statement = connection.prepare(
+        "SELECT Name, Department, Change, datetime(ChangedAt, 'unixepoch') AS ChangedAt
+        FROM (
+            SELECT Name, Department, 'Joined' AS Change, FirstSeenAt AS ChangedAt
+            FROM StudentHistory
+            WHERE (Version = 1 AND FirstSeenAt > (
+                    SELECT MIN(Earliest.FirstSeenAt) FROM StudentHistory AS Earliest
+                    WHERE Earliest.CollegeId = StudentHistory.CollegeId
+                ))
+                OR ChangedFields LIKE '%Department%'
+                OR ChangedFields LIKE '%College%'
+                OR ChangedFields LIKE '%Returned%'
+            UNION ALL
+            SELECT Previous.Name, Previous.Department, 'Left', Current.FirstSeenAt
+            FROM StudentHistory AS Current
+            JOIN StudentHistory AS Previous
+            ON Previous.StudentId = Current.StudentId AND Previous.Version = Current.Version - 1
+            WHERE Previous.AbsentSince IS NULL
+                AND (Current.ChangedFields LIKE '%Department%'
+                    OR Current.ChangedFields LIKE '%College%')
+            UNION ALL
+            SELECT Name, Department, 'Left', AbsentSince
+            FROM StudentHistory
+            WHERE AbsentSince IS NOT NULL
+        )
+        ORDER BY ChangedAt DESC, Name ASC, Change ASC
+        LIMIT ?1",
+    )?;
+    let mut changes_query = changes_statement.query([limit])?;
+    let mut changes = vec![];
+
+    while let Some(row) = changes_query.next()? {
+        changes.push(DepartmentChange {
+            name: row.get("Name")?,
+            department: row.get("Department")?,
+            change: row.get("Change")?,
+            changed_at: row.get("ChangedAt")?,
+        });
+    }
+
+    Ok(changes)
+}
+
+fn fetch_latest_version(transaction: &Transaction, student_id: &str) -> Option<StudentVersion> {
+    transaction
+        .query_row(
+            "SELECT Version, Name, Email, Department, CollegeId, AbsentSince
+            FROM StudentHistory
+            WHERE StudentId = ?1
+            ORDER BY Version DESC
+            LIMIT 1",
+            [student_id],
+            |row| {
+                Ok(StudentVersion {
+                    version: row.get("Version")?,
+                    name: row.get("Name")?,
+                    email: row.get("Email")?,
+                    department: row.get("Department")?,
+                    college_id: row.get("CollegeId")?,
+                    absent_since: row.get("AbsentSince")?,
+                })
+            },
+        )
+        .optional()
+        .unwrap()
+}
+
+fn insert_version(
+    transaction: &Transaction,
+    run_id: &str,
+    seen_at: u64,
+    college: &College,
+    student: &GraduateStudent,
+    version: usize,
+    changed_fields: &str,
+) {
+    transaction
+        .execute(
+            "INSERT INTO StudentHistory
+            (StudentId, Version, Name, Email, Department, CollegeId,
+                FirstSeenAt, LastSeenAt, LastSeenRunId, ChangedFields)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7, ?8, ?9)",
+            params![
+                student.id,
+                version,
+                student.names.join(" "),
+                student.email,
+                student.department,
+                college.id,
+                seen_at,
+                run_id,
+                changed_fields
+            ],
+        )
+        .unwrap();
+}
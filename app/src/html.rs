@@ -1,7 +1,9 @@
 use scraper::{ElementRef, Html, Selector};
+use serde::Deserialize;
 
 use crate::error::Status;
 
+#[derive(Deserialize, Clone)]
 pub struct ScrapperSelectors {
     pub directory_row_selector: String,
     pub name_selectors: Vec<String>,
@@ -13,6 +15,7 @@ pub struct ScrapperSelectors {
 
 #[derive(Debug, Default)]
 pub struct DirectoryRow<'a> {
+    pub element: Option<ElementRef<'a>>,
     pub name_elements: Vec<ElementRef<'a>>,
     pub position_element: Option<ElementRef<'a>>,
     pub department_element: Option<ElementRef<'a>>,
@@ -52,6 +55,7 @@ pub fn scrape_html<'a>(
     Ok(dom
         .select(&directory_row_selector)
         .map(|entry| DirectoryRow {
+            element: Some(entry),
             position_element: position_selector
                 .as_ref()
                 .and_then(|selector| entry.select(&selector).next()),
@@ -1,6 +1,10 @@
+use anyhow::{anyhow, Error};
+use base64::{engine::general_purpose::STANDARD, Engine};
 use serde::de::DeserializeOwned;
 use tiny_http::{Header, Request};
 
+use crate::error::Status;
+
 pub fn find_header<'a>(request: &'a Request, name: &str) -> Option<&'a Header> {
     request
         .headers()
@@ -8,6 +12,13 @@ pub fn find_header<'a>(request: &'a Request, name: &str) -> Option<&'a Header> {
         .find(|header| header.field.to_string().to_lowercase() == name.to_lowercase())
 }
 
+// Finds a header htmx sends with every request, so a missing one means the request did not
+// come from the page
+pub fn require_header<'a>(request: &'a Request, name: &str) -> Result<&'a Header, Status> {
+    find_header(request, name)
+        .ok_or_else(|| Status::InvalidArgument(anyhow!("Missing {} header", name)))
+}
+
 pub fn extract_query<T>(url: &str) -> Result<T, serde_qs::Error>
 where
     T: DeserializeOwned,
@@ -15,12 +26,69 @@ where
     serde_qs::from_str::<T>(&url.to_string().split("?").skip(1).next().unwrap_or(""))
 }
 
-pub fn parse_form_data<T>(request: &mut Request) -> Result<T, serde_urlencoded::de::Error>
+// Checks the password of a basic authorization header against a shared secret, ignoring
+// the user name. The comparison takes the same time wherever the passwords differ
+pub fn require_shared_secret(request: &Request, shared_secret: &str) -> Result<(), Status> {
+    let password = find_header(request, "Authorization")
+        .and_then(|header| header.value.as_str().strip_prefix("Basic "))
+        .and_then(|credentials| STANDARD.decode(credentials.trim()).ok())
+        .and_then(|credentials| String::from_utf8(credentials).ok())
+        .and_then(|credentials| {
+            credentials
+                .split_once(':')
+                .map(|(_, password)| password.to_string())
+        })
+        .ok_or_else(|| Status::Unauthenticated(anyhow!("Enter the organizer password")))?;
+
+    if shared_secret.is_empty() || !constant_time_eq(password.as_bytes(), shared_secret.as_bytes())
+    {
+        return Err(Status::Unauthenticated(anyhow!("Wrong organizer password")));
+    }
+
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+pub fn parse_form_data<T>(request: &mut Request) -> Result<T, Status>
 where
     T: DeserializeOwned,
 {
+    parse_form_body(&read_body(request)?)
+}
+
+pub fn parse_form_body<T>(body: &str) -> Result<T, Status>
+where
+    T: DeserializeOwned,
+{
+    serde_urlencoded::from_str(body)
+        .map_err(|error| Status::InvalidArgument(anyhow!("Failed to parse form data: {}", error)))
+}
+
+pub fn read_body(request: &mut Request) -> Result<String, Status> {
     let mut body = String::new();
-    request.as_reader().read_to_string(&mut body).unwrap();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|error| {
+            Status::InvalidArgument(Error::from(error).context("Failed to read form data"))
+        })?;
+
+    Ok(body)
+}
 
-    serde_urlencoded::from_str(&body)
+// Spreadsheets run a cell that starts like a formula, so exported text that came from
+// scraped pages or form input is prefixed with a quote to keep it as text
+pub fn escape_formula(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
 }
@@ -1,15 +1,30 @@
+pub mod admin;
 pub mod agriculture;
+pub mod api;
+pub mod auth;
 pub mod college;
 pub mod configuration;
 pub mod directory;
 pub mod error;
+pub mod filter;
 pub mod health;
+pub mod history;
 pub mod html;
 pub mod http;
 pub mod id;
 pub mod liberal_arts;
+pub mod match_review;
+pub mod matching;
+pub mod member;
 pub mod parser;
 pub mod pipeline;
+pub mod raises;
+pub mod registry;
+pub mod responses;
 pub mod salary;
 pub mod scraper;
+pub mod search;
 pub mod server;
+pub mod shutdown;
+pub mod sign_up;
+pub mod statistics;
@@ -7,10 +7,10 @@ use scraper::{ElementRef, Html};
 
 use crate::{
     college::GraduateStudent,
-    error::Status,
+    error::{RowError, Status},
     html::{scrape_html, ScrapperSelectors},
     parser::HtmlRowParser,
-    scraper::StudentScraper,
+    scraper::{parse_directory_row, StudentScraper},
 };
 
 pub struct LiberalArtsScrapper {
@@ -79,11 +79,17 @@ impl StudentScraper<(), String> for LiberalArtsScrapper {
         )?
         .iter()
         .filter_map(|row| {
-            let Some(mut student) = parser.parse_row(row) else {
-                return None;
+            let mut student = match parse_directory_row(&parser, row)? {
+                Ok(student) => student,
+                Err(status) => return Some(Err(status)),
             };
             let Some(positions) = parser.parse_positions(&row.position_element) else {
-                return None;
+                return Some(Err(Status::InvalidArgument(Error::from(RowError::new(
+                    "Failed to parse positions",
+                    row.element
+                        .map(|element| element.html())
+                        .unwrap_or_default(),
+                )))));
             };
             student.department = positions
                 .into_iter()
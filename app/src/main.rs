@@ -1,9 +1,11 @@
-use std::sync::Arc;
+use std::sync::{atomic::AtomicUsize, Arc};
 
 use perdue::{
     configuration::{read_configuration, Configuration},
     pipeline::start_pipeline,
+    registry::read_sources,
     server::{start_server, ServerState},
+    shutdown::Shutdown,
 };
 use r2d2_sqlite::SqliteConnectionManager;
 
@@ -12,6 +14,8 @@ use r2d2_sqlite::SqliteConnectionManager;
 async fn main() {
     let configuration: Configuration = read_configuration("ENVIRONMENT", "CONFIGURATION_PATH")
         .unwrap_or_else(|error| panic!("{}", error.to_string()));
+    let sources = read_sources(&configuration.files.sources_path)
+        .unwrap_or_else(|error| panic!("{}", error.to_string()));
     let pool_manager =
         SqliteConnectionManager::file(configuration.database.connection_type.as_str());
     let connection_pool = r2d2::Pool::builder()
@@ -21,6 +25,8 @@ async fn main() {
     let state = Arc::new(ServerState {
         connection_pool: connection_pool.clone(),
         configuration,
+        sources,
+        live_workers: AtomicUsize::new(0),
     });
     let connection = connection_pool.get().unwrap();
     let version: usize = connection
@@ -28,8 +34,20 @@ async fn main() {
         .unwrap_or(0);
     println!("Current migration version: {version}");
 
-    start_pipeline(state.clone());
-    start_server(state.clone());
+    let shutdown = Shutdown::listen_for_signals();
+    let pipeline = start_pipeline(state.clone(), shutdown.clone());
+    let supervisor = start_server(state.clone(), shutdown);
+    let server = tokio::task::spawn_blocking(move || {
+        if supervisor.join().is_err() {
+            eprintln!("The server supervisor panicked");
+        }
+    });
+
+    let (pipeline, server) = tokio::join!(pipeline, server);
+
+    if let Err(error) = pipeline.and(server) {
+        eprintln!("Failed to shut down cleanly: {}", error);
+    }
 
-    loop {}
+    println!("Server stopped");
 }
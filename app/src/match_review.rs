@@ -0,0 +1,387 @@
+use std::{io::Cursor, str::FromStr, sync::Arc};
+
+use anyhow::anyhow;
+use askama::Template;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::Deserialize;
+use tiny_http::{Header, Request, Response};
+
+use crate::{
+    error::Status,
+    filter::escape_like,
+    http::{extract_query, parse_form_data},
+    matching::StudentIndex,
+    pipeline::unix_timestamp,
+    salary::{format_usd, SalaryKey},
+    server::{empty_fragment, ServerState},
+};
+
+const REVIEW_PAGE_SIZE: usize = 50;
+const LOW_CONFIDENCE: f64 = 0.8;
+const SUGGESTED_CANDIDATES: usize = 5;
+
+#[derive(Template)]
+#[template(path = "match_review.html")]
+pub struct MatchReview {
+    pub search: String,
+    pub rows: Vec<MatchReviewRow>,
+}
+
+pub struct MatchReviewRow {
+    pub reason: String,
+    pub year: usize,
+    pub name: String,
+    pub department: String,
+    pub amount: String,
+    pub candidates: Vec<MatchReviewCandidate>,
+}
+
+pub struct MatchReviewCandidate {
+    pub student_id: String,
+    pub name: String,
+    pub department: String,
+    pub confidence: String,
+    pub method: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct MatchReviewQuery {
+    search: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct MatchOverrideRequest {
+    year: usize,
+    name: String,
+    department: String,
+    student_id: Option<String>,
+}
+
+impl MatchOverrideRequest {
+    fn key(&self) -> SalaryKey {
+        SalaryKey {
+            year: self.year,
+            name: self.name.clone(),
+            department: self.department.clone(),
+        }
+    }
+}
+
+// Lists salary rows that are ambiguous, unmatched or matched with low confidence next
+// to the students they could belong to
+pub fn display_match_review(
+    request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let connection = context.connection_pool.get()?;
+    let query: MatchReviewQuery = extract_query(request.url()).map_err(|error| {
+        Status::InvalidArgument(anyhow!("Failed to parse match review query: {}", error))
+    })?;
+    let search = query.search.unwrap_or_default();
+    let pattern = format!("%{}%", escape_like(&search));
+    let student_index = StudentIndex::load(&context.connection_pool);
+    let mut rows = fetch_ambiguous_rows(&pattern, &connection)?;
+    rows.append(&mut fetch_low_confidence_rows(&pattern, &connection)?);
+    rows.append(&mut fetch_unmatched_rows(
+        &pattern,
+        &student_index,
+        &connection,
+    )?);
+
+    Ok(
+        Response::from_string(MatchReview { search, rows }.to_string())
+            .with_header(Header::from_str("Content-Type: text/html").unwrap()),
+    )
+}
+
+pub fn confirm_salary_match(
+    request: &mut Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let match_override: MatchOverrideRequest = parse_form_data(request)?;
+
+    override_salary_match(&match_override, "Confirmed", context)
+}
+
+pub fn reassign_salary_match(
+    request: &mut Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let match_override: MatchOverrideRequest = parse_form_data(request)?;
+
+    override_salary_match(&match_override, "Reassigned", context)
+}
+
+pub fn reject_salary_match(
+    request: &mut Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let mut match_override: MatchOverrideRequest = parse_form_data(request)?;
+    match_override.student_id = None;
+
+    override_salary_match(&match_override, "Rejected", context)
+}
+
+// Stores the override so later pipeline runs keep it and applies it to the salary right away
+fn override_salary_match(
+    match_override: &MatchOverrideRequest,
+    action: &str,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let mut connection = context.connection_pool.get()?;
+    let transaction = connection.transaction()?;
+    let key = match_override.key();
+    let amount_usd = fetch_salary_amount(&key, &transaction)?
+        .ok_or_else(|| Status::NotFound(anyhow!("Unknown salary")))?;
+    let student_id = match_override
+        .student_id
+        .as_ref()
+        .map(|student_id| student_id.trim())
+        .filter(|student_id| !student_id.is_empty());
+
+    if action != "Rejected" && student_id.is_none() {
+        return Err(Status::InvalidArgument(anyhow!("Missing student")));
+    }
+
+    if let Some(student_id) = student_id {
+        let student_exists: bool = transaction.query_row(
+            "SELECT EXISTS(SELECT 1 FROM Students WHERE Id = ?1)",
+            [student_id],
+            |row| row.get(0),
+        )?;
+
+        if !student_exists {
+            return Err(Status::InvalidArgument(anyhow!("Unknown student")));
+        }
+    }
+
+    transaction.execute(
+        "INSERT OR REPLACE INTO SalaryMatchOverrides
+            (Year, Name, Department, StudentId, Action, UpdatedAt)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            key.year,
+            key.name,
+            key.department,
+            student_id,
+            action,
+            unix_timestamp()
+        ],
+    )?;
+    transaction.execute(
+        "DELETE FROM Salaries WHERE Year = ?1 AND SalaryName = ?2 AND SalaryDepartment = ?3",
+        params![key.year, key.name, key.department],
+    )?;
+
+    for table in [
+        "AmbiguousSalaryCandidates",
+        "AmbiguousSalaries",
+        "UnmatchedSalaries",
+    ] {
+        transaction.execute(
+            &format!("DELETE FROM {table} WHERE Year = ?1 AND Name = ?2 AND Department = ?3"),
+            params![key.year, key.name, key.department],
+        )?;
+    }
+
+    if let Some(student_id) = student_id {
+        // The row for this salary is gone by now, so any salary left for the student that
+        // year is another payroll row the override would overwrite
+        let has_other_salary: bool = transaction.query_row(
+            "SELECT EXISTS(SELECT 1 FROM Salaries WHERE StudentId = ?1 AND Year = ?2)",
+            params![student_id, key.year],
+            |row| row.get(0),
+        )?;
+
+        if has_other_salary {
+            return Err(Status::InvalidArgument(anyhow!(
+                "Student already has a salary in {}, review that salary first",
+                key.year
+            )));
+        }
+
+        transaction.execute(
+            "INSERT INTO Salaries
+                (StudentId, Year, AmountUsd, SalaryName, SalaryDepartment, Confidence, Method)
+                VALUES (?1, ?2, ?3, ?4, ?5, 1.0, ?6)",
+            params![
+                student_id,
+                key.year,
+                amount_usd,
+                key.name,
+                key.department,
+                action
+            ],
+        )?;
+    }
+
+    transaction.commit()?;
+
+    Ok(empty_fragment())
+}
+
+fn fetch_salary_amount(
+    key: &SalaryKey,
+    transaction: &Transaction,
+) -> Result<Option<usize>, Status> {
+    let amount_usd = transaction
+        .query_row(
+            "SELECT AmountUsd FROM AmbiguousSalaries
+            WHERE Year = ?1 AND Name = ?2 AND Department = ?3
+            UNION ALL
+            SELECT AmountUsd FROM UnmatchedSalaries
+            WHERE Year = ?1 AND Name = ?2 AND Department = ?3
+            UNION ALL
+            SELECT AmountUsd FROM Salaries
+            WHERE Year = ?1 AND SalaryName = ?2 AND SalaryDepartment = ?3
+            LIMIT 1",
+            params![key.year, key.name, key.department],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(amount_usd)
+}
+
+fn fetch_ambiguous_rows(
+    pattern: &str,
+    connection: &Connection,
+) -> Result<Vec<MatchReviewRow>, Status> {
+    let mut statement = connection.prepare(
+        "SELECT Year, Name, Department, AmountUsd FROM AmbiguousSalaries
+            WHERE Name LIKE ?1 ESCAPE '\\'
+            ORDER BY Name ASC, Year DESC
+            LIMIT ?2",
+    )?;
+    let mut candidates_statement = connection.prepare(
+        "SELECT StudentId, Students.Name, Students.Department, Confidence, Method
+            FROM AmbiguousSalaryCandidates
+            JOIN Students
+            ON Students.Id = AmbiguousSalaryCandidates.StudentId
+            WHERE Year = ?1 AND AmbiguousSalaryCandidates.Name = ?2
+            AND AmbiguousSalaryCandidates.Department = ?3
+            ORDER BY Confidence DESC",
+    )?;
+    let mut query = statement.query(params![pattern, REVIEW_PAGE_SIZE])?;
+    let mut rows = vec![];
+
+    while let Some(row) = query.next()? {
+        let mut review_row = MatchReviewRow {
+            reason: String::from("Ambiguous"),
+            year: row.get("Year")?,
+            name: row.get("Name")?,
+            department: row.get("Department")?,
+            amount: format_usd(row.get("AmountUsd")?),
+            candidates: vec![],
+        };
+        let mut candidates_query = candidates_statement.query(params![
+            review_row.year,
+            review_row.name,
+            review_row.department
+        ])?;
+
+        while let Some(candidate) = candidates_query.next()? {
+            review_row.candidates.push(MatchReviewCandidate {
+                student_id: candidate.get("StudentId")?,
+                name: candidate.get("Name")?,
+                department: candidate.get("Department")?,
+                confidence: format!("{:.2}", candidate.get::<_, f64>("Confidence")?),
+                method: candidate.get("Method")?,
+            });
+        }
+
+        rows.push(review_row);
+    }
+
+    Ok(rows)
+}
+
+fn fetch_low_confidence_rows(
+    pattern: &str,
+    connection: &Connection,
+) -> Result<Vec<MatchReviewRow>, Status> {
+    let mut statement = connection.prepare(
+        "SELECT Year, SalaryName, SalaryDepartment, AmountUsd, StudentId,
+                Students.Name, Students.Department, Confidence, Method
+            FROM Salaries
+            JOIN Students
+            ON Students.Id = Salaries.StudentId
+            WHERE Confidence < ?1 AND SalaryName LIKE ?2 ESCAPE '\\'
+            ORDER BY Confidence ASC, SalaryName ASC
+            LIMIT ?3",
+    )?;
+    let mut query = statement.query(params![LOW_CONFIDENCE, pattern, REVIEW_PAGE_SIZE])?;
+    let mut rows = vec![];
+
+    while let Some(row) = query.next()? {
+        rows.push(MatchReviewRow {
+            reason: String::from("Low confidence"),
+            year: row.get("Year")?,
+            name: row.get("SalaryName")?,
+            department: row.get("SalaryDepartment")?,
+            amount: format_usd(row.get("AmountUsd")?),
+            candidates: vec![MatchReviewCandidate {
+                student_id: row.get("StudentId")?,
+                name: row.get("Name")?,
+                department: row.get("Department")?,
+                confidence: format!("{:.2}", row.get::<_, f64>("Confidence")?),
+                method: row.get("Method")?,
+            }],
+        });
+    }
+
+    Ok(rows)
+}
+
+// Unmatched rows have no stored candidates, so suggest the students that share the family
+// name even when they scored below the match threshold
+fn fetch_unmatched_rows(
+    pattern: &str,
+    student_index: &StudentIndex,
+    connection: &Connection,
+) -> Result<Vec<MatchReviewRow>, Status> {
+    let mut statement = connection.prepare(
+        "SELECT Year, Name, Department, AmountUsd FROM UnmatchedSalaries
+            WHERE Name LIKE ?1 ESCAPE '\\'
+            ORDER BY Name ASC, Year DESC
+            LIMIT ?2",
+    )?;
+    let mut student_statement =
+        connection.prepare("SELECT Name, Department FROM Students WHERE Id = ?1")?;
+    let mut query = statement.query(params![pattern, REVIEW_PAGE_SIZE])?;
+    let mut rows = vec![];
+
+    while let Some(row) = query.next()? {
+        let name: String = row.get("Name")?;
+        let department: String = row.get("Department")?;
+        let candidates = student_index
+            .find_candidates(&name, &department)
+            .into_iter()
+            .take(SUGGESTED_CANDIDATES)
+            .filter_map(|candidate| {
+                student_statement
+                    .query_row([&candidate.student_id], |student| {
+                        Ok(MatchReviewCandidate {
+                            student_id: candidate.student_id.clone(),
+                            name: student.get("Name")?,
+                            department: student.get("Department")?,
+                            confidence: format!("{:.2}", candidate.confidence),
+                            method: candidate.method.as_str().to_string(),
+                        })
+                    })
+                    .ok()
+            })
+            .collect();
+
+        rows.push(MatchReviewRow {
+            reason: String::from("Unmatched"),
+            year: row.get("Year")?,
+            name,
+            department,
+            amount: format_usd(row.get("AmountUsd")?),
+            candidates,
+        });
+    }
+
+    Ok(rows)
+}
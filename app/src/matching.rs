@@ -0,0 +1,289 @@
+use std::collections::{HashMap, HashSet};
+
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+const NAME_SUFFIXES: [&str; 7] = ["jr", "sr", "ii", "iii", "iv", "phd", "md"];
+const DEPARTMENT_STOP_WORDS: [&str; 14] = [
+    "wl",
+    "of",
+    "and",
+    "the",
+    "for",
+    "in",
+    "department",
+    "dept",
+    "school",
+    "college",
+    "program",
+    "engineering",
+    "engr",
+    "sciences",
+];
+const MATCH_THRESHOLD: f64 = 0.6;
+const AMBIGUITY_MARGIN: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMethod {
+    Exact,
+    Initial,
+    Prefix,
+    PartialFamilyName,
+}
+
+impl MatchMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchMethod::Exact => "Exact",
+            MatchMethod::Initial => "Initial",
+            MatchMethod::Prefix => "Prefix",
+            MatchMethod::PartialFamilyName => "PartialFamilyName",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchCandidate {
+    pub student_id: String,
+    pub confidence: f64,
+    pub method: MatchMethod,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SalaryMatch {
+    Matched(MatchCandidate),
+    Ambiguous(Vec<MatchCandidate>),
+    Unmatched,
+}
+
+pub struct IndexedStudent {
+    pub id: String,
+    pub names: Vec<String>,
+    pub department: Vec<String>,
+}
+
+// Scraped students indexed by every normalized name token so salary rows only score
+// students that share part of their family name
+pub struct StudentIndex {
+    students: Vec<IndexedStudent>,
+    by_name: HashMap<String, Vec<usize>>,
+}
+
+impl StudentIndex {
+    pub fn new(students: Vec<(String, String, String)>) -> StudentIndex {
+        let mut index = StudentIndex {
+            students: vec![],
+            by_name: HashMap::new(),
+        };
+
+        for (id, name, department) in students {
+            let names = normalize_name(&name);
+
+            for name in &names {
+                index
+                    .by_name
+                    .entry(name.clone())
+                    .or_default()
+                    .push(index.students.len());
+            }
+
+            index.students.push(IndexedStudent {
+                id,
+                names,
+                department: normalize_department(&department),
+            });
+        }
+
+        index
+    }
+
+    pub fn load(connection_pool: &Pool<SqliteConnectionManager>) -> StudentIndex {
+        let connection = connection_pool.get().unwrap();
+        let mut statement = connection
+            .prepare("SELECT Id, Name, Department FROM Students")
+            .unwrap();
+        let students = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .flatten()
+            .collect();
+
+        StudentIndex::new(students)
+    }
+
+    // Matches a gateway name such as "Doe, Jane A" against the scraped students, leaving
+    // the row unmatched when more than one student scores about as well as the best
+    pub fn match_salary(&self, name: &str, department: &str) -> SalaryMatch {
+        let candidates: Vec<MatchCandidate> = self
+            .find_candidates(name, department)
+            .into_iter()
+            .filter(|candidate| candidate.confidence >= MATCH_THRESHOLD)
+            .collect();
+
+        match candidates.as_slice() {
+            [] => SalaryMatch::Unmatched,
+            [best] => SalaryMatch::Matched(best.clone()),
+            [best, second, ..] if best.confidence - second.confidence >= AMBIGUITY_MARGIN => {
+                SalaryMatch::Matched(best.clone())
+            }
+            _ => SalaryMatch::Ambiguous(candidates),
+        }
+    }
+
+    // Scores every student sharing part of the family name, best candidates first
+    pub fn find_candidates(&self, name: &str, department: &str) -> Vec<MatchCandidate> {
+        let Some((family_name, given_names)) = name.split_once(',') else {
+            return vec![];
+        };
+        let family_names = normalize_name(family_name);
+        let given_names = normalize_name(given_names);
+        let department = normalize_department(department);
+        let mut student_indices: Vec<usize> = family_names
+            .iter()
+            .filter_map(|name| self.by_name.get(name))
+            .flatten()
+            .copied()
+            .collect::<HashSet<usize>>()
+            .into_iter()
+            .collect();
+        student_indices.sort();
+
+        let mut candidates: Vec<MatchCandidate> = student_indices
+            .into_iter()
+            .filter_map(|index| {
+                score_candidate(
+                    &family_names,
+                    &given_names,
+                    &department,
+                    &self.students[index],
+                )
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+        candidates
+    }
+}
+
+// Lowercases, strips diacritics and punctuation, splits hyphenated names and drops
+// suffixes such as "Jr" or "III"
+pub fn normalize_name(name: &str) -> Vec<String> {
+    name.nfd()
+        .filter(|character| !is_combining_mark(*character))
+        .flat_map(|character| character.to_lowercase())
+        .map(|character| match character {
+            'ø' => 'o',
+            'ł' => 'l',
+            'đ' => 'd',
+            'ı' => 'i',
+            character if character.is_alphanumeric() => character,
+            _ => ' ',
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .filter(|name| !NAME_SUFFIXES.contains(name))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+// Reduces department names like "WL - Electrical &amp; Com" or "School of Electrical
+// and Computer Engineering" to their distinguishing words
+pub fn normalize_department(department: &str) -> Vec<String> {
+    department
+        .replace("&amp;", " ")
+        .split(|character: char| !character.is_alphanumeric())
+        .map(|word| word.to_lowercase())
+        .filter(|word| word.len() > 2 && !DEPARTMENT_STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+// The gateway truncates department names, so words only need to share a prefix
+pub fn is_department_consistent(salary_department: &[String], department: &[String]) -> bool {
+    salary_department.iter().any(|salary_word| {
+        department.iter().any(|word| {
+            word.starts_with(salary_word.as_str()) || salary_word.starts_with(word.as_str())
+        })
+    })
+}
+
+fn score_candidate(
+    family_names: &[String],
+    given_names: &[String],
+    department: &[String],
+    student: &IndexedStudent,
+) -> Option<MatchCandidate> {
+    let matching_family_names = family_names
+        .iter()
+        .filter(|name| student.names.contains(name))
+        .count();
+
+    if matching_family_names == 0 {
+        return None;
+    }
+
+    let first_name = given_names.first()?;
+    let student_given_names: Vec<&String> = student
+        .names
+        .iter()
+        .filter(|name| !family_names.contains(name))
+        .collect();
+    let student_first_name = student_given_names.first()?;
+    let (first_name_score, mut method) = match compare_given_name(first_name, student_first_name)? {
+        GivenNameMatch::Exact => (1.0, MatchMethod::Exact),
+        GivenNameMatch::Prefix => (0.7, MatchMethod::Prefix),
+        GivenNameMatch::Initial => (0.6, MatchMethod::Initial),
+    };
+    let family_name_score = if matching_family_names == family_names.len() {
+        1.0
+    } else {
+        method = MatchMethod::PartialFamilyName;
+        0.75
+    };
+    let middle_name_score = match (given_names.get(1), student_given_names.get(1)) {
+        (Some(middle_name), Some(student_middle_name)) => {
+            match compare_given_name(middle_name, student_middle_name) {
+                Some(_) => 0.05,
+                None => -0.1,
+            }
+        }
+        _ => 0.0,
+    };
+    let department_score = if is_department_consistent(department, &student.department) {
+        0.15
+    } else {
+        0.0
+    };
+    let confidence: f64 =
+        0.45 * family_name_score + 0.4 * first_name_score + middle_name_score + department_score;
+
+    Some(MatchCandidate {
+        student_id: student.id.clone(),
+        confidence: confidence.clamp(0.0, 1.0),
+        method,
+    })
+}
+
+enum GivenNameMatch {
+    Exact,
+    Prefix,
+    Initial,
+}
+
+fn compare_given_name(name: &str, other: &str) -> Option<GivenNameMatch> {
+    let (shorter, longer) = if name.len() <= other.len() {
+        (name, other)
+    } else {
+        (other, name)
+    };
+
+    if shorter == longer {
+        Some(GivenNameMatch::Exact)
+    } else if shorter.chars().count() == 1 && longer.starts_with(shorter) {
+        Some(GivenNameMatch::Initial)
+    } else if shorter.chars().count() >= 3 && longer.starts_with(shorter) {
+        Some(GivenNameMatch::Prefix)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,197 @@
+use std::{collections::BTreeMap, io::Cursor, str::FromStr, sync::Arc};
+
+use anyhow::anyhow;
+use askama::Template;
+use rusqlite::{Connection, OptionalExtension};
+use tiny_http::{Header, Request, Response};
+
+use crate::{
+    college::Office,
+    directory::build_department_directory_url,
+    error::Status,
+    raises::{fetch_student_compensation, format_change_row, CompensationChangeRow},
+    salary::format_usd,
+    server::ServerState,
+    statistics::{
+        build_histogram, count_histogram_bins, histogram_bin_width, summarize_sorted, Histogram,
+    },
+};
+
+#[derive(Template)]
+#[template(path = "member_page.html")]
+pub struct MemberPage {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    pub department: String,
+    pub college_id: String,
+    pub college_name: String,
+    pub offices: Vec<Office>,
+    pub directory_url: String,
+    pub salaries: Vec<MemberSalaryRow>,
+    pub histogram: Option<Histogram>,
+}
+
+pub struct MemberSalaryRow {
+    pub change: CompensationChangeRow,
+    pub department_median: String,
+    pub department_position: String,
+}
+
+struct Member {
+    name: String,
+    email: String,
+    department: String,
+    college_id: String,
+    college_name: String,
+}
+
+// Shows a student's scraped details and every salary year next to the salaries of their
+// department in the same college
+pub fn display_member(
+    request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let student_id = request
+        .url()
+        .split('?')
+        .next()
+        .and_then(|path| path.strip_prefix("/member/"))
+        .filter(|student_id| !student_id.is_empty() && !student_id.contains('/'))
+        .ok_or_else(|| Status::NotFound(anyhow!("Missing student id")))?;
+    let connection = context.connection_pool.get()?;
+    let member = connection
+        .query_row(
+            "SELECT Students.Name, Email, Department, CollegeId, College.Name AS CollegeName
+            FROM Students
+            LEFT JOIN College
+            ON College.Id = Students.CollegeId
+            WHERE Students.Id = ?1",
+            [student_id],
+            |row| {
+                Ok(Member {
+                    name: row.get("Name")?,
+                    email: row.get("Email")?,
+                    department: row.get("Department")?,
+                    college_id: row
+                        .get::<_, Option<String>>("CollegeId")?
+                        .unwrap_or_default(),
+                    college_name: row
+                        .get::<_, Option<String>>("CollegeName")?
+                        .unwrap_or_default(),
+                })
+            },
+        )
+        .optional()?
+        .ok_or_else(|| Status::NotFound(anyhow!("Unknown student '{}'", student_id)))?;
+    let department_salaries = fetch_department_salaries(&member, &connection)?;
+    let changes = fetch_student_compensation(
+        student_id,
+        &context.configuration.statistics.cpi,
+        &connection,
+    )?;
+    let histogram = changes.last().and_then(|latest| {
+        let amounts = department_salaries.get(&latest.year)?;
+        let bin_width = histogram_bin_width(*amounts.last()?);
+
+        Some(build_histogram(
+            format!("{} in {}", member.department, latest.year),
+            &count_histogram_bins(amounts, bin_width),
+            bin_width,
+            latest.amount_usd,
+            format!("{} {}", member.name, format_usd(latest.amount_usd)),
+        ))
+    });
+    let salaries = changes
+        .iter()
+        .rev()
+        .map(|change| {
+            let amounts = department_salaries
+                .get(&change.year)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let below = amounts.partition_point(|amount| *amount < change.amount_usd);
+
+            MemberSalaryRow {
+                change: format_change_row(change),
+                department_median: summarize_sorted(amounts)
+                    .map(|summary| format_usd(summary.median))
+                    .unwrap_or_else(|| String::from("-")),
+                department_position: format_department_position(below, amounts.len()),
+            }
+        })
+        .collect();
+
+    Ok(Response::from_string(
+        MemberPage {
+            id: student_id.to_string(),
+            offices: fetch_offices(student_id, &connection)?,
+            directory_url: build_department_directory_url(&member.college_id, &member.department)?,
+            salaries,
+            histogram,
+            name: member.name,
+            email: member.email,
+            department: member.department,
+            college_id: member.college_id,
+            college_name: member.college_name,
+        }
+        .to_string(),
+    )
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
+}
+
+// The department salaries include the member's own, which is left out of the others they
+// are compared against
+fn format_department_position(below: usize, department_size: usize) -> String {
+    match department_size.saturating_sub(1) {
+        0 => String::from("Only student paid in the department"),
+        1 => format!("Paid more than {} of 1 other", below),
+        others => format!("Paid more than {} of {} others", below, others),
+    }
+}
+
+// Salaries of the member's department in their college by year, sorted in ascending order
+fn fetch_department_salaries(
+    member: &Member,
+    connection: &Connection,
+) -> Result<BTreeMap<usize, Vec<usize>>, Status> {
+    let mut statement = connection.prepare(
+        "SELECT Year, AmountUsd
+        FROM Salaries
+        JOIN Students
+        ON Students.Id = Salaries.StudentId
+        WHERE Department = ?1 AND CollegeId = ?2
+        ORDER BY Year ASC, AmountUsd ASC",
+    )?;
+    let mut query = statement.query([&member.department, &member.college_id])?;
+    let mut salaries: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+
+    while let Some(row) = query.next()? {
+        salaries
+            .entry(row.get("Year")?)
+            .or_default()
+            .push(row.get("AmountUsd")?);
+    }
+
+    Ok(salaries)
+}
+
+fn fetch_offices(student_id: &str, connection: &Connection) -> Result<Vec<Office>, Status> {
+    let mut statement = connection.prepare(
+        "SELECT Building, Room FROM Offices
+        WHERE StudentId = ?1
+        ORDER BY Building ASC, Room ASC",
+    )?;
+    let offices = statement
+        .query_map([student_id], |row| {
+            Ok(Office {
+                building: row
+                    .get::<_, Option<String>>("Building")?
+                    .unwrap_or_default(),
+                room: row.get::<_, Option<String>>("Room")?.unwrap_or_default(),
+            })
+        })?
+        .collect::<Result<Vec<Office>, rusqlite::Error>>()?;
+
+    Ok(offices)
+}
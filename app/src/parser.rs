@@ -1,7 +1,7 @@
 use scraper::ElementRef;
 
 use crate::{
-    college::{GraduateStudent, Office},
+    college::{College, GraduateStudent, Office},
     html::DirectoryRow,
 };
 
@@ -134,6 +134,24 @@ pub struct BiologicalSciencesParser;
 
 pub struct StatisticsParser;
 
+// Looks up a parser by the name it is registered under in the college sources
+pub fn find_parser(name: &str, college: &College) -> Option<Box<dyn HtmlRowParser>> {
+    match name {
+        "Default" => Some(Box::new(DefaultRowParser {
+            default_department: college.default_department.clone(),
+            default_office: college.default_office.clone(),
+        })),
+        "LastNameFirst" => Some(Box::new(LastNameFirstParser {})),
+        "Pharmacy" => Some(Box::new(PharmacyParser {})),
+        "ChemicalSciences" => Some(Box::new(ChemicalSciencesParser {})),
+        "PhysicsAndAstronomy" => Some(Box::new(PhysicsAndAstronomyParser {})),
+        "VeterinaryMedicine" => Some(Box::new(VeterinaryMedicineParser {})),
+        "BiologicalSciences" => Some(Box::new(BiologicalSciencesParser {})),
+        "Statistics" => Some(Box::new(StatisticsParser {})),
+        _ => None,
+    }
+}
+
 impl HtmlRowParser for DefaultRowParser {
     fn parse_department(&self, element: &Option<ElementRef<'_>>) -> Option<String> {
         let Some(element) = element else {
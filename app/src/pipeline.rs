@@ -1,561 +1,381 @@
-use std::sync::Arc;
+use std::{
+    panic::AssertUnwindSafe,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use tokio::task::JoinSet;
+use anyhow::anyhow;
+use futures::{future::BoxFuture, FutureExt};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use reqwest::Client;
+use rusqlite::params;
+use tokio::{
+    task::{JoinHandle, JoinSet},
+    time::{interval, MissedTickBehavior},
+};
 
 use crate::{
     agriculture::AgricultureScraper,
-    college::{store_students, College, Office},
+    college::{store_college, store_students, College},
+    error::Status,
     health::HealthScrapper,
-    html::ScrapperSelectors,
+    history::{mark_absent_students, record_student_history},
+    id::generate_id,
     liberal_arts::LiberalArtsScrapper,
-    parser::{
-        BiologicalSciencesParser, ChemicalSciencesParser, DefaultRowParser, PharmacyParser,
-        PhysicsAndAstronomyParser, StatisticsParser, VeterinaryMedicineParser,
-    },
+    parser::find_parser,
+    registry::{CollegeSource, ScraperKind},
     salary::{process_salaries, store_salaries},
-    scraper::{scrape_college, SinglePageStudentScrapper},
-    server::ServerState,
+    scraper::{scrape_college, ScrapedCollege, SinglePageStudentScrapper},
+    search::store_student_search,
+    server::{panic_message, ServerState},
+    shutdown::Shutdown,
 };
 
-pub fn start_pipeline(state: Arc<ServerState>) {
+type ScrapeTasks = JoinSet<ScrapedCollege>;
+
+const MAX_SNIPPET_LENGTH: usize = 2048;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl RunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "Running",
+            Self::Succeeded => "Succeeded",
+            Self::Failed => "Failed",
+            Self::Cancelled => "Cancelled",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CollegeRunCounts {
+    pub scraped: usize,
+    pub errors: usize,
+    pub inserted: usize,
+    pub updated: usize,
+}
+
+// Runs the pipeline at boot and then again every configured interval until a shutdown
+// is requested
+pub fn start_pipeline(state: Arc<ServerState>, mut shutdown: Shutdown) -> JoinHandle<()> {
     tokio::spawn(async move {
-        println!("Pipeline Start");
-        run(&state).await;
-        println!("Pipeline Done");
-    });
+        let mut schedule = interval(Duration::from_secs(
+            state.configuration.pipeline.interval_seconds,
+        ));
+        schedule.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = schedule.tick() => (),
+                _ = shutdown.requested() => break,
+            }
+            // A run that cannot be recorded is skipped and tried again on the next tick
+            if let Err(error) = run_pipeline(&state, &shutdown).await {
+                eprintln!("Failed to record pipeline run: {}", error);
+            }
+        }
+    })
 }
 
-async fn run(state: &Arc<ServerState>) {
+// Runs the pipeline once, recording the run and how it ended, and returns the run id
+pub async fn run_pipeline(state: &Arc<ServerState>, shutdown: &Shutdown) -> Result<String, Status> {
+    println!("Pipeline Start");
+    let run_id = start_run(&state.connection_pool)?;
+    // Runs in its own task so a panicking run is recorded instead of stopping the schedule
+    let status = match tokio::spawn(run(state.clone(), run_id.clone(), shutdown.clone())).await {
+        Ok(status) => status,
+        Err(error) => {
+            eprintln!("Pipeline run {} failed: {}", run_id, error);
+            RunStatus::Failed
+        }
+    };
+    finish_run(&run_id, status, &state.connection_pool)?;
+    println!("Pipeline Done");
+
+    Ok(run_id)
+}
+
+async fn run(state: Arc<ServerState>, run_id: String, mut shutdown: Shutdown) -> RunStatus {
     let client = Arc::new(reqwest::Client::new());
+    let started_at = unix_timestamp();
+    let mut status = RunStatus::Succeeded;
+    let mut scraped_colleges = vec![];
 
     println!("Processing students...");
     let mut scrape_tasks = JoinSet::new();
 
-    println!("Scraping college of agriculture...");
-    let agriculture_college = College {
-        id: String::from("0"),
-        name: String::from("College of Agriculture"),
-        base_url: String::from(
-            "https://ag.purdue.edu/api/pi/2021/api/Directory/ListStaffDirectory",
-        ),
-        default_department: String::from("School of Agriculture"),
-        default_office: Office::default(),
-    };
-    scrape_tasks.spawn(scrape_college(Arc::new(AgricultureScraper {
-        http_client: client.clone(),
-        base_url: agriculture_college.base_url,
-    })));
-
-    println!("Scraping college of education...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("1"),
-            name: String::from("College of Education"),
-            base_url: String::from("https://education.purdue.edu/graduate-directory/"),
-            default_department: String::from("School of Education"),
-            default_office: Office::default(),
-        },
-        parser: Box::new(DefaultRowParser {
-            default_department: String::from("School of Education"),
-            default_office: Office::default(),
-        }),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".grad-directory-archive-container"),
-            position_selector: Some(String::from(".position")),
-            name_selectors: vec![String::from(".grad-directory-archive-info h2")],
-            email_selector: Some(String::from(".grad-directory-archive-contact a")),
-            department_selector: Some(String::from(".department")),
-            location_selector: None,
-        },
-    })));
-
-    println!("Scraping college of health...");
-    scrape_tasks.spawn(scrape_college(HealthScrapper::new(
-        "https://hhs.purdue.edu/wp-admin/admin-ajax.php",
-        client.clone(),
-    )));
-
-    println!("Scraping college of liberal arts...");
-    let liberal_arts_college = College {
-        id: String::from("2"),
-        name: String::from("College of Liberal Arts"),
-        base_url: String::from("https://cla.purdue.edu/directory/"),
-        default_office: Office::default(),
-        default_department: String::from("School of Liberal Arts"),
-    };
-    scrape_tasks.spawn(scrape_college(Arc::new(LiberalArtsScrapper {
-        client: client.clone(),
-        url: liberal_arts_college.base_url,
-    })));
-
-    println!("Scraping college of pharmacy...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("3"),
-            name: String::from("College of Pharmacy"),
-            base_url: String::from(
-                "https://www.pharmacy.purdue.edu/directory?name=&dept=&type=gradstudent",
-            ),
-            default_department: String::from("School of Pharmacy"),
-            default_office: Office::default(),
-        },
-        parser: Box::new(PharmacyParser {}),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from("table tbody tr"),
-            name_selectors: vec![String::from("td:nth-child(1)")],
-            position_selector: Some(String::from("td:nth-child(2)")),
-            location_selector: Some(String::from("td:nth-child(3)")),
-            email_selector: Some(String::from("td:nth-child(5) a")),
-            department_selector: None,
-        },
-    })));
-
-    println!("Scraping college of biomedical engineering...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("4"),
-            name: String::from("College of Biomedial Engineering"),
-            base_url: String::from("https://engineering.purdue.edu/BME/People/GradStudents"),
-            default_office: Office {
-                building: String::from("Hall of Biomedical Engineering"),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Biomedical Engineering"),
-        },
-        parser: Box::new(DefaultRowParser {
-            default_office: Office {
-                building: String::from("Hall of Biomedical Engineering"),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Biomedical Engineering"),
-        }),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".people-list .row"),
-            name_selectors: vec![
-                String::from(".list-name a"),
-                String::from(".list-name strong"),
-            ],
-            department_selector: None,
-            email_selector: Some(String::from(".email a")),
-            location_selector: None,
-            position_selector: Some(String::from(".people-list-title")),
-        },
-    })));
-
-    println!("Scraping college of chemical engineering...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("5"),
-            name: String::from("College of Chemical Engineering"),
-            base_url: String::from("https://engineering.purdue.edu/ChE/people/ptGradStudents"),
-            default_office: Office {
-                building: String::from("Forney Hall of Chemical Engineering"),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Chemical Engineering"),
-        },
-        parser: Box::new(DefaultRowParser {
-            default_office: Office {
-                building: String::from("Forney Hall of Chemical Engineering"),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Chemical Engineering"),
-        }),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".people-list .row"),
-            name_selectors: vec![String::from(".list-name")],
-            department_selector: None,
-            email_selector: Some(String::from(".email a")),
-            location_selector: None,
-            position_selector: Some(String::from(".people-list-title")),
-        },
-    })));
-
-    println!("Scraping college of engineering education...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("6"),
-            name: String::from("College of Engineering Education"),
-            base_url: String::from("https://engineering.purdue.edu/ENE/People/GraduateStudents"),
-            default_office: Office {
-                building: String::from("Armstrong Hall"),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Engineering Education"),
-        },
-        parser: Box::new(DefaultRowParser {
-            default_office: Office {
-                building: String::from("Armstrong Hall"),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Engineering Education"),
-        }),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".people-list .row"),
-            name_selectors: vec![
-                String::from(".list-name a"),
-                String::from(".list-name strong"),
-            ],
-            department_selector: None,
-            email_selector: Some(String::from(".email a")),
-            location_selector: None,
-            position_selector: Some(String::from(".title")),
-        },
-    })));
-
-    println!("Scraping college of environmental and ecological engineering...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("7"),
-            name: String::from("College of Ecological Engineering"),
-            base_url: String::from("https://engineering.purdue.edu/EEE/People/Graduate"),
-            default_office: Office::default(),
-            default_department: String::from("School of Environmental and Ecological Engineering"),
-        },
-        parser: Box::new(DefaultRowParser {
-            default_office: Office::default(),
-            default_department: String::from("School of Environmental and Ecological Engineering"),
-        }),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".people-list .row"),
-            name_selectors: vec![
-                String::from(".list-name a"),
-                String::from(".list-name strong"),
-            ],
-            department_selector: None,
-            email_selector: Some(String::from(".people-list-pyEmail a")),
-            location_selector: None,
-            position_selector: Some(String::from(".people-list-title")),
-        },
-    })));
-
-    println!("Scraping college of industrial engineering...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("8"),
-            name: String::from("College of Industrial Engineering"),
-            base_url: String::from("https://engineering.purdue.edu/IE/people/Grad"),
-            default_office: Office {
-                building: String::from("Grissom Hall"),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Industrial Engineering"),
-        },
-        parser: Box::new(DefaultRowParser {
-            default_office: Office {
-                building: String::from("Grissom Hall"),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Industrial Engineering"),
-        }),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".people-list .row"),
-            name_selectors: vec![
-                String::from(".list-name a"),
-                String::from(".list-name span"),
-            ],
-            department_selector: None,
-            email_selector: Some(String::from(".email a")),
-            location_selector: None,
-            position_selector: Some(String::from(".people-list-title")),
-        },
-    })));
-
-    println!("Scraping college of materials engineering...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("9"),
-            name: String::from("College of Materials Engineering"),
-            base_url: String::from(
-                "https://engineering.purdue.edu/MSE/academics/graduate/graduate-directory/index_html",
-            ),
-            default_office: Office {
-                building: String::from(""),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Materials Engineering"),
-        },
-        parser: Box::new(DefaultRowParser {
-            default_office: Office {
-                building: String::from(""),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Materials Engineering"),
-        }),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".mse-grad-card"),
-            name_selectors: vec![String::from("h1")],
-            department_selector: None,
-            email_selector: Some(String::from("a")),
-            location_selector: None,
-            position_selector: None,
-        },
-    })));
-
-    println!("Scraping college of nuclear engineering...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("10"),
-            name: String::from("College of Nuclear Engineering"),
-            base_url: String::from("https://engineering.purdue.edu/NE/people/grads"),
-            default_office: Office {
-                building: String::from(""),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Nuclear Engineering"),
-        },
-        parser: Box::new(DefaultRowParser {
-            default_office: Office {
-                building: String::from(""),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Nuclear Engineering"),
-        }),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".people-list .row"),
-            name_selectors: vec![
-                String::from(".list-name a"),
-                String::from(".list-name strong"),
-            ],
-            department_selector: None,
-            email_selector: Some(String::from(".email a")),
-            location_selector: None,
-            position_selector: None,
-        },
-    })));
-
-    println!("Scraping college of biological sciences...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("11"),
-            name: String::from("College of Biological Sciences"),
-            base_url: String::from("https://www.bio.purdue.edu/People/graduate_students.html"),
-            default_office: Office {
-                building: String::from("LILY"),
-                room: String::from(""),
-            },
-            default_department: String::from("School of Biological Sciences"),
-        },
-        parser: Box::new(BiologicalSciencesParser {}),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from("#container .element"),
-            name_selectors: vec![String::from("h2")],
-            department_selector: None,
-            email_selector: Some(String::from("div:nth-child(2) p:nth-child(6) a")),
-            location_selector: Some(String::from("div:nth-child(2) p:nth-child(4)")),
-            position_selector: None,
-        },
-    })));
-
-    println!("Scraping college of chemical sciences...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("12"),
-            name: String::from("College of Chemical Sciences"),
-            base_url: String::from("https://www.chem.purdue.edu/people/internal.html"),
-            default_office: Office {
-                building: String::from("BRWN"),
-                room: String::from(""),
-            },
-            default_department: String::from("Department Of Chemistry"),
-        },
-        parser: Box::new(ChemicalSciencesParser {}),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".table tbody tr"),
-            name_selectors: vec![String::from("td:nth-child(3)")],
-            department_selector: None,
-            email_selector: Some(String::from("td:nth-child(4) a")),
-            location_selector: Some(String::from("td:nth-child(7)")),
-            position_selector: None,
-        },
-    })));
-
-    println!("Scraping college of computer sciences...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("12"),
-            name: String::from("College of Computer Sciences"),
-            base_url: String::from("https://www.cs.purdue.edu/people/graduate-students/index.html"),
-            default_office: Office {
-                building: String::from("LWSN"),
-                room: String::from(""),
-            },
-            default_department: String::from("Department of Computer Science"),
-        },
-        parser: Box::new(DefaultRowParser {
-            default_office: Office {
-                building: String::from("LWSN"),
-                room: String::from(""),
-            },
-            default_department: String::from("Department of Computer Science"),
-        }),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".table tbody tr"),
-            name_selectors: vec![String::from("td:nth-child(1)")],
-            department_selector: None,
-            email_selector: Some(String::from("td:nth-child(3) a")),
-            location_selector: Some(String::from("td:nth-child(2)")),
-            position_selector: None,
-        },
-    })));
-
-    println!("Scraping college of Earth, Atmospheric, and Planatary Sciences...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("13"),
-            name: String::from("College of Earth, Atmospheric, and Planatary Sciences"),
-            base_url: String::from("https://www.eaps.purdue.edu/people/grad/index.php"),
-            default_office: Office {
-                building: String::from("HAMP"),
-                room: String::from(""),
-            },
-            default_department: String::from("School of EAPS"),
-        },
-        parser: Box::new(DefaultRowParser {
-            default_office: Office {
-                building: String::from("HAMP"),
-                room: String::from(""),
-            },
-            default_department: String::from("School of EAPS"),
-        }),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".PhD .peopleDirectoryPerson"),
-            name_selectors: vec![String::from(".peopleDirectoryInfo strong")],
-            department_selector: None,
-            email_selector: Some(String::from(".peopleDirectoryInfo a")),
-            location_selector: Some(String::from(".peopleDirectoryInfo div")),
-            position_selector: None,
-        },
-    })));
-
-    println!("Scraping college of mathematics...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("14"),
-            name: String::from("College of Mathematics"),
-            base_url: String::from("https://www.math.purdue.edu/people/gradstudents.html"),
-            default_office: Office {
-                building: String::from("MATH"),
-                room: String::from(""),
-            },
-            default_department: String::from("Department of Mathematics"),
-        },
-        parser: Box::new(DefaultRowParser {
-            default_office: Office {
-                building: String::from("MATH"),
-                room: String::from(""),
-            },
-            default_department: String::from("Department of Mathematics"),
-        }),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from("#container .directory-row"),
-            name_selectors: vec![String::from(".peopleDirectoryName a")],
-            department_selector: None,
-            email_selector: Some(String::from(".st_details li a")),
-            location_selector: Some(String::from(".st_details li:nth-child(2)")),
-            position_selector: None,
-        },
-    })));
-
-    println!("Scraping college of physics and astronomy...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("15"),
-            name: String::from("College of Physics and Astronomy"),
-            base_url: String::from(
-                "https://www.physics.purdue.edu/php-scripts/people/people_list.php",
-            ),
-            default_office: Office {
-                building: String::from("PHYS"),
-                room: String::from(""),
-            },
-            default_department: String::from("Department of Physics and Astronomy"),
-        },
-        parser: Box::new(PhysicsAndAstronomyParser {}),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".person-item"),
-            name_selectors: vec![String::from("h2")],
-            department_selector: None,
-            email_selector: Some(String::from(".email_link")),
-            location_selector: Some(String::from(".info-box div:nth-child(2) .info")),
-            position_selector: Some(String::from("a[data-category=\"graduate\"]")),
-        },
-    })));
-
-    println!("Scraping college of statistics...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("16"),
-            name: String::from("College of Statistics"),
-            base_url: String::from("https://www.stat.purdue.edu/people/graduate_students/"),
-            default_office: Office {
-                building: String::from("MATH"),
-                room: String::from(""),
-            },
-            default_department: String::from("Department of Statistics"),
-        },
-        parser: Box::new(StatisticsParser {}),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from("#container .element"),
-            name_selectors: vec![String::from("div h2")],
-            department_selector: None,
-            email_selector: Some(String::from("div div p a")),
-            location_selector: Some(String::from("div div p:nth-child(1)")),
-            position_selector: None,
-        },
-    })));
-
-    println!("Scraping college of veterinary medice...");
-    scrape_tasks.spawn(scrape_college(Arc::new(SinglePageStudentScrapper {
-        client: client.clone(),
-        college: College {
-            id: String::from("17"),
-            name: String::from("College of Vererinary Medicine"),
-            base_url: String::from("https://vet.purdue.edu/directory/index.php?classification=20"),
-            default_office: Office {
-                building: String::from(""),
-                room: String::from(""),
-            },
-            default_department: String::from("Department of Veterinary Medicine"),
-        },
-        parser: Box::new(VeterinaryMedicineParser {}),
-        selector: ScrapperSelectors {
-            directory_row_selector: String::from(".profile-entry"),
-            name_selectors: vec![String::from("div:nth-child(1) a")],
-            department_selector: None,
-            email_selector: Some(String::from("div:nth-child(3) a")),
-            location_selector: None,
-            position_selector: None,
-        },
-    })));
-
-    while let Some(Ok(Ok(scraped_students_by_page))) = scrape_tasks.join_next().await {
-        println!("Storing students...");
-        for page in scraped_students_by_page {
-            store_students(&page, &state.connection_pool);
+    for source in &state.sources {
+        println!("Scraping {}...", source.college.name);
+        spawn_scrape(&mut scrape_tasks, source, client.clone());
+    }
+
+    loop {
+        // Colleges are stored as they finish scraping, so a shutdown only interrupts the
+        // scrapes still in flight and the students stay consistent
+        let scraped_college = tokio::select! {
+            scraped_college = scrape_tasks.join_next() => scraped_college,
+            _ = shutdown.requested() => {
+                scrape_tasks.abort_all();
+                println!("Pipeline cancelled");
+                return RunStatus::Cancelled;
+            }
+        };
+        let Some(scraped_college) = scraped_college else {
+            break;
+        };
+        let Ok(scraped_college) = scraped_college else {
+            status = RunStatus::Failed;
+            continue;
+        };
+        let college = scraped_college.college;
+        let mut counts = CollegeRunCounts::default();
+
+        store_college(&college, &state.connection_pool);
+
+        match scraped_college.pages {
+            Ok(pages) => {
+                println!("Storing students for {}...", college.name);
+                for page in pages {
+                    let stored = store_students(&college, &page.students, &state.connection_pool);
+                    let errors: Vec<&Status> = page
+                        .students
+                        .iter()
+                        .filter_map(|student| student.as_ref().err())
+                        .collect();
+                    counts.scraped += page.students.len() - errors.len();
+                    counts.errors += errors.len();
+                    counts.inserted += stored.inserted;
+                    counts.updated += stored.updated;
+                    record_student_history(
+                        &run_id,
+                        started_at,
+                        &college,
+                        &page.students,
+                        &state.connection_pool,
+                    );
+                    if let Err(error) = store_scrape_errors(
+                        &run_id,
+                        &college,
+                        Some(page.number),
+                        &errors,
+                        &state.connection_pool,
+                    ) {
+                        eprintln!(
+                            "Failed to store scrape errors for {}: {}",
+                            college.name, error
+                        );
+                        status = RunStatus::Failed;
+                    }
+                }
+
+                scraped_colleges.push(college.clone());
+            }
+            Err(error) => {
+                eprintln!("Failed to scrape {}: {}", college.name, error);
+                counts.errors += 1;
+                status = RunStatus::Failed;
+                if let Err(error) =
+                    store_scrape_errors(&run_id, &college, None, &[&error], &state.connection_pool)
+                {
+                    eprintln!(
+                        "Failed to store scrape errors for {}: {}",
+                        college.name, error
+                    );
+                }
+            }
+        }
+
+        if let Err(error) = store_run_college(&run_id, &college, &counts, &state.connection_pool) {
+            eprintln!("Failed to store run counts for {}: {}", college.name, error);
+            status = RunStatus::Failed;
+        }
+    }
+
+    // Only colleges that scraped fully can tell us which of their students are gone
+    for college in &scraped_colleges {
+        let absent = mark_absent_students(&run_id, started_at, college, &state.connection_pool);
+
+        if absent > 0 {
+            println!(
+                "{} students are no longer listed by {}",
+                absent, college.name
+            );
         }
     }
 
     println!("Done storing students...");
+    store_student_search(&state.connection_pool);
     println!("Done processing students...");
     println!("Processing salaries...");
-    let salaries = process_salaries(&state.connection_pool, &state.configuration.files.salaries_path);
+    let salaries = process_salaries(
+        &state.connection_pool,
+        &state.configuration.files.salaries_directory,
+    );
     store_salaries(&salaries, &state.connection_pool);
     println!("Done processing salaries...");
+
+    status
+}
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn start_run(connection_pool: &Pool<SqliteConnectionManager>) -> Result<String, Status> {
+    let run_id = generate_id();
+
+    connection_pool.get()?.execute(
+        "INSERT INTO PipelineRuns (RunId, StartedAt, Status) VALUES (?1, ?2, ?3)",
+        params![run_id, unix_timestamp(), RunStatus::Running.as_str()],
+    )?;
+
+    Ok(run_id)
+}
+
+fn finish_run(
+    run_id: &str,
+    status: RunStatus,
+    connection_pool: &Pool<SqliteConnectionManager>,
+) -> Result<(), Status> {
+    connection_pool.get()?.execute(
+        "UPDATE PipelineRuns SET EndedAt = ?1, Status = ?2 WHERE RunId = ?3",
+        params![unix_timestamp(), status.as_str(), run_id],
+    )?;
+
+    Ok(())
+}
+
+fn store_run_college(
+    run_id: &str,
+    college: &College,
+    counts: &CollegeRunCounts,
+    connection_pool: &Pool<SqliteConnectionManager>,
+) -> Result<(), Status> {
+    connection_pool.get()?.execute(
+        "INSERT OR REPLACE INTO PipelineRunColleges
+        (RunId, CollegeId, Scraped, Errors, Inserted, Updated)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            run_id,
+            college.id,
+            counts.scraped,
+            counts.errors,
+            counts.inserted,
+            counts.updated
+        ],
+    )?;
+
+    Ok(())
+}
+
+// Records scrape errors, counting an error already seen in an earlier run as a repeat
+fn store_scrape_errors(
+    run_id: &str,
+    college: &College,
+    page: Option<usize>,
+    errors: &[&Status],
+    connection_pool: &Pool<SqliteConnectionManager>,
+) -> Result<(), Status> {
+    let connection = connection_pool.get()?;
+    let mut error_statement = connection.prepare(
+        "INSERT INTO ScrapeErrors
+            (ErrorId, CollegeId, Page, Kind, Message, Snippet, FirstRunId, LastRunId, Occurrences)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7, 1)
+            ON CONFLICT(CollegeId, Kind, Message, Snippet) DO UPDATE SET
+                Page = excluded.Page,
+                Occurrences = Occurrences + (LastRunId != excluded.LastRunId),
+                LastRunId = excluded.LastRunId",
+    )?;
+
+    for error in errors {
+        let snippet: String = error
+            .snippet()
+            .unwrap_or_default()
+            .chars()
+            .take(MAX_SNIPPET_LENGTH)
+            .collect();
+
+        error_statement.execute(params![
+            generate_id(),
+            college.id,
+            page,
+            error.kind(),
+            error.message(),
+            snippet,
+            run_id
+        ])?;
+    }
+
+    Ok(())
+}
+
+// Builds the scraper described by a college source and queues it on the scrape tasks
+fn spawn_scrape(scrape_tasks: &mut ScrapeTasks, source: &CollegeSource, client: Arc<Client>) {
+    let college = source.college.clone();
+    let scrape: BoxFuture<'static, ScrapedCollege> = match source.scraper {
+        ScraperKind::Agriculture => scrape_college(
+            source.college.clone(),
+            Arc::new(AgricultureScraper {
+                http_client: client,
+                base_url: source.college.base_url.clone(),
+            }),
+        )
+        .boxed(),
+        ScraperKind::Health => scrape_college(
+            source.college.clone(),
+            HealthScrapper::new(&source.college.base_url, client),
+        )
+        .boxed(),
+        ScraperKind::LiberalArts => scrape_college(
+            source.college.clone(),
+            LiberalArtsScrapper::new(&source.college.base_url, client),
+        )
+        .boxed(),
+        ScraperKind::SinglePage => {
+            // Sources are validated when the registry is read so these are always present
+            let parser = source
+                .parser
+                .as_ref()
+                .and_then(|parser| find_parser(parser, &source.college))
+                .unwrap();
+            let selector = source.selectors.clone().unwrap();
+
+            scrape_college(
+                source.college.clone(),
+                Arc::new(SinglePageStudentScrapper {
+                    client,
+                    college: source.college.clone(),
+                    parser,
+                    selector,
+                }),
+            )
+            .boxed()
+        }
+    };
+
+    // A scraper that panics is recorded as a failed scrape of its college, so the run
+    // history still says which college failed
+    scrape_tasks.spawn(async move {
+        AssertUnwindSafe(scrape)
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|panic| ScrapedCollege {
+                college,
+                pages: Err(Status::Internal(anyhow!(
+                    "Scraper panicked: {}",
+                    panic_message(panic.as_ref())
+                ))),
+            })
+    });
 }
@@ -0,0 +1,325 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    io::Cursor,
+    str::FromStr,
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use askama::Template;
+use rusqlite::{Connection, OptionalExtension};
+use tiny_http::{Header, Request, Response};
+
+use crate::{
+    error::Status,
+    salary::{format_usd, format_usd_change},
+    server::ServerState,
+    statistics::median,
+};
+
+#[derive(Template)]
+#[template(path = "student_compensation.html")]
+pub struct StudentCompensationPage {
+    pub name: String,
+    pub department: String,
+    pub rows: Vec<CompensationChangeRow>,
+}
+
+#[derive(Template)]
+#[template(path = "department_raises.html")]
+pub struct DepartmentRaisesPage {
+    pub rows: Vec<DepartmentRaiseRow>,
+}
+
+pub struct CompensationChangeRow {
+    pub year: usize,
+    pub amount: String,
+    pub change: String,
+    pub change_percent: String,
+    pub real_change: String,
+    pub real_change_percent: String,
+}
+
+pub struct DepartmentRaiseRow {
+    pub college: String,
+    pub department: String,
+    pub year: usize,
+    pub student_count: usize,
+    pub raise_count: usize,
+    pub cut_count: usize,
+    pub median_change: String,
+    pub median_change_percent: String,
+    pub median_real_change_percent: String,
+}
+
+// A salary year compared against the same student's salary in the year before, with the
+// previous salary moved into this year's dollars for the real change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompensationChange {
+    pub year: usize,
+    pub amount_usd: usize,
+    pub change_cents: Option<i64>,
+    pub change_ratio: Option<f64>,
+    pub real_change_cents: Option<i64>,
+    pub real_change_ratio: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepartmentRaise {
+    pub college_id: String,
+    pub college_name: String,
+    pub department: String,
+    pub year: usize,
+    pub student_count: usize,
+    pub raise_count: usize,
+    pub cut_count: usize,
+    pub median_change_cents: Option<f64>,
+    pub median_change_ratio: Option<f64>,
+    pub median_real_change_ratio: Option<f64>,
+}
+
+// Compares each salary year against the year right before it. Years after a gap have no
+// change since they are not a year-over-year comparison
+pub fn compute_compensation_changes(
+    salaries: &[(usize, usize)],
+    cpi: &BTreeMap<usize, f64>,
+) -> Vec<CompensationChange> {
+    let mut changes = vec![];
+    let mut previous: Option<(usize, usize)> = None;
+
+    for &(year, amount_usd) in salaries {
+        let change = match previous {
+            Some(previous) if previous.0 + 1 == year => {
+                compare_years(previous, (year, amount_usd), cpi)
+            }
+            _ => CompensationChange {
+                year,
+                amount_usd,
+                change_cents: None,
+                change_ratio: None,
+                real_change_cents: None,
+                real_change_ratio: None,
+            },
+        };
+        changes.push(change);
+        previous = Some((year, amount_usd));
+    }
+
+    changes
+}
+
+// Real changes are left out when the CPI table is missing either year
+pub fn compare_years(
+    (previous_year, previous_amount): (usize, usize),
+    (year, amount_usd): (usize, usize),
+    cpi: &BTreeMap<usize, f64>,
+) -> CompensationChange {
+    let ratio = |previous: f64| (previous > 0.0).then(|| amount_usd as f64 / previous - 1.0);
+    let adjusted_previous = cpi
+        .get(&previous_year)
+        .zip(cpi.get(&year))
+        .filter(|(previous_cpi, _)| **previous_cpi > 0.0)
+        .map(|(previous_cpi, cpi)| previous_amount as f64 * cpi / previous_cpi);
+
+    CompensationChange {
+        year,
+        amount_usd,
+        change_cents: Some(amount_usd as i64 - previous_amount as i64),
+        change_ratio: ratio(previous_amount as f64),
+        real_change_cents: adjusted_previous
+            .map(|adjusted_previous| (amount_usd as f64 - adjusted_previous).round() as i64),
+        real_change_ratio: adjusted_previous.and_then(ratio),
+    }
+}
+
+pub fn fetch_student_compensation(
+    student_id: &str,
+    cpi: &BTreeMap<usize, f64>,
+    connection: &Connection,
+) -> Result<Vec<CompensationChange>, Status> {
+    let mut statement = connection.prepare(
+        "SELECT Year, AmountUsd FROM Salaries
+        WHERE StudentId = ?1
+        ORDER BY Year ASC",
+    )?;
+    let salaries = statement
+        .query_map([student_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(usize, usize)>, rusqlite::Error>>()?;
+
+    Ok(compute_compensation_changes(&salaries, cpi))
+}
+
+// Summarizes the change of every student paid in consecutive years by their current
+// college and department and the later year, with the latest years of a department first
+pub fn fetch_department_raises(
+    cpi: &BTreeMap<usize, f64>,
+    connection: &Connection,
+) -> Result<Vec<DepartmentRaise>, Status> {
+    let mut statement = connection.prepare(
+        "SELECT Students.CollegeId, COALESCE(College.Name, Students.CollegeId) AS CollegeName,
+            Students.Department, Previous.Year AS PreviousYear,
+            Previous.AmountUsd AS PreviousAmountUsd, Current.Year, Current.AmountUsd
+        FROM Salaries AS Current
+        JOIN Salaries AS Previous
+        ON Previous.StudentId = Current.StudentId AND Previous.Year = Current.Year - 1
+        JOIN Students
+        ON Students.Id = Current.StudentId
+        LEFT JOIN College
+        ON College.Id = Students.CollegeId",
+    )?;
+    let mut query = statement.query([])?;
+    let mut groups: HashMap<(String, String, String, usize), Vec<CompensationChange>> =
+        HashMap::new();
+
+    while let Some(row) = query.next()? {
+        let change = compare_years(
+            (row.get("PreviousYear")?, row.get("PreviousAmountUsd")?),
+            (row.get("Year")?, row.get("AmountUsd")?),
+            cpi,
+        );
+        let key = (
+            row.get::<_, Option<String>>("CollegeId")?
+                .unwrap_or_default(),
+            row.get::<_, Option<String>>("CollegeName")?
+                .unwrap_or_default(),
+            row.get("Department")?,
+            change.year,
+        );
+        groups.entry(key).or_default().push(change);
+    }
+
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    // Ordered by college name, department and latest year first
+    groups.sort_by_cached_key(|((college_id, college, department, year), _)| {
+        (
+            college.clone(),
+            college_id.clone(),
+            department.clone(),
+            Reverse(*year),
+        )
+    });
+
+    Ok(groups
+        .into_iter()
+        .map(|((college_id, college_name, department, year), changes)| {
+            let collect = |value: fn(&CompensationChange) -> Option<f64>| {
+                changes.iter().filter_map(value).collect::<Vec<f64>>()
+            };
+
+            DepartmentRaise {
+                college_id,
+                college_name,
+                department,
+                year,
+                student_count: changes.len(),
+                raise_count: changes
+                    .iter()
+                    .filter(|change| change.change_cents.is_some_and(|cents| cents > 0))
+                    .count(),
+                cut_count: changes
+                    .iter()
+                    .filter(|change| change.change_cents.is_some_and(|cents| cents < 0))
+                    .count(),
+                median_change_cents: median(&mut collect(|change| {
+                    change.change_cents.map(|cents| cents as f64)
+                })),
+                median_change_ratio: median(&mut collect(|change| change.change_ratio)),
+                median_real_change_ratio: median(&mut collect(|change| change.real_change_ratio)),
+            }
+        })
+        .collect())
+}
+
+pub fn display_student_compensation(
+    request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let student_id = request
+        .url()
+        .split('?')
+        .next()
+        .and_then(|path| path.strip_prefix("/students/"))
+        .and_then(|path| path.strip_suffix("/compensation"))
+        .filter(|student_id| !student_id.is_empty() && !student_id.contains('/'))
+        .ok_or_else(|| Status::NotFound(anyhow!("Missing student id")))?;
+    let connection = context.connection_pool.get()?;
+    let (name, department) = connection
+        .query_row(
+            "SELECT Name, Department FROM Students WHERE Id = ?1",
+            [student_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| Status::NotFound(anyhow!("Unknown student '{}'", student_id)))?;
+    let changes = fetch_student_compensation(
+        student_id,
+        &context.configuration.statistics.cpi,
+        &connection,
+    )?;
+
+    Ok(Response::from_string(
+        StudentCompensationPage {
+            name,
+            department,
+            rows: changes.iter().rev().map(format_change_row).collect(),
+        }
+        .to_string(),
+    )
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
+}
+
+pub fn display_department_raises(
+    _request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let connection = context.connection_pool.get()?;
+    let raises = fetch_department_raises(&context.configuration.statistics.cpi, &connection)?;
+
+    Ok(Response::from_string(
+        DepartmentRaisesPage {
+            rows: raises
+                .into_iter()
+                .map(|raise| DepartmentRaiseRow {
+                    median_change: raise
+                        .median_change_cents
+                        .map(|cents| format_usd_change(cents.round() as i64))
+                        .unwrap_or_else(|| String::from("-")),
+                    median_change_percent: format_percent(raise.median_change_ratio),
+                    median_real_change_percent: format_percent(raise.median_real_change_ratio),
+                    college: raise.college_name,
+                    department: raise.department,
+                    year: raise.year,
+                    student_count: raise.student_count,
+                    raise_count: raise.raise_count,
+                    cut_count: raise.cut_count,
+                })
+                .collect(),
+        }
+        .to_string(),
+    )
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
+}
+
+pub fn format_change_row(change: &CompensationChange) -> CompensationChangeRow {
+    let format_cents = |cents: Option<i64>| {
+        cents
+            .map(format_usd_change)
+            .unwrap_or_else(|| String::from("-"))
+    };
+
+    CompensationChangeRow {
+        year: change.year,
+        amount: format_usd(change.amount_usd),
+        change: format_cents(change.change_cents),
+        change_percent: format_percent(change.change_ratio),
+        real_change: format_cents(change.real_change_cents),
+        real_change_percent: format_percent(change.real_change_ratio),
+    }
+}
+
+fn format_percent(ratio: Option<f64>) -> String {
+    ratio
+        .map(|ratio| format!("{:+.1}%", ratio * 100.0))
+        .unwrap_or_else(|| String::from("-"))
+}
@@ -0,0 +1,68 @@
+use std::{collections::HashSet, fs::File};
+
+use anyhow::{anyhow, Error};
+use serde::Deserialize;
+
+use crate::{college::College, html::ScrapperSelectors, parser::find_parser};
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ScraperKind {
+    SinglePage,
+    Agriculture,
+    Health,
+    LiberalArts,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct CollegeSource {
+    #[serde(flatten)]
+    pub college: College,
+    pub scraper: ScraperKind,
+    pub parser: Option<String>,
+    pub selectors: Option<ScrapperSelectors>,
+}
+
+// Reads the college source registry and checks every entry can be turned into a scraper
+pub fn read_sources(sources_path: &str) -> Result<Vec<CollegeSource>, Error> {
+    let file = File::open(sources_path).map_err(|error| {
+        Error::from(error).context(format!("Failed to open college sources {}", sources_path))
+    })?;
+    let sources: Vec<CollegeSource> = serde_json::from_reader(file).map_err(|error| {
+        Error::from(error).context(format!("Failed to parse college sources {}", sources_path))
+    })?;
+    let mut college_ids = HashSet::new();
+
+    for source in &sources {
+        if !college_ids.insert(source.college.id.as_str()) {
+            return Err(anyhow!("Duplicate college id '{}'", source.college.id));
+        }
+
+        validate_source(source)?;
+    }
+
+    Ok(sources)
+}
+
+fn validate_source(source: &CollegeSource) -> Result<(), Error> {
+    if source.scraper != ScraperKind::SinglePage {
+        return Ok(());
+    }
+
+    let Some(parser) = &source.parser else {
+        return Err(anyhow!("{} is missing a parser", source.college.name));
+    };
+
+    if find_parser(parser, &source.college).is_none() {
+        return Err(anyhow!(
+            "{} uses unregistered parser '{}'",
+            source.college.name,
+            parser
+        ));
+    }
+
+    if source.selectors.is_none() {
+        return Err(anyhow!("{} is missing selectors", source.college.name));
+    }
+
+    Ok(())
+}
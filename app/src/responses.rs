@@ -0,0 +1,379 @@
+use std::{io::Cursor, str::FromStr, sync::Arc};
+
+use anyhow::anyhow;
+use askama::Template;
+use rusqlite::{params_from_iter, types::Value, Connection, Row};
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Request, Response};
+
+use crate::{
+    error::Status,
+    http::{escape_formula, extract_query},
+    salary::format_usd,
+    server::ServerState,
+    sign_up::KEY_ISSUES,
+};
+
+const CONTACT_LIMIT: usize = 500;
+const EXPORT_HEADERS: [&str; 11] = [
+    "Name",
+    "Purdue Email",
+    "Personal Email",
+    "Phone Number",
+    "Department",
+    "Office",
+    "Latest Compensation",
+    "Responded",
+    "Graduate Student",
+    "Mailing List",
+    "Key Issues",
+];
+// Every scraped student with their response, if any, followed by responses that could not
+// be linked to a student
+const CONTACTS: &str = "WITH Contacts AS (
+        SELECT Students.Id AS StudentId, Students.Name, Students.Email, Students.Department,
+            Responses.ResponseId, Responses.GraduateStudent, Responses.PersonalEmail,
+            Responses.PhoneNumber, Responses.MailingList, Responses.KeyIssues
+        FROM Students
+        LEFT JOIN Responses
+        ON Responses.StudentId = Students.Id
+        UNION ALL
+        SELECT NULL, FirstAndLastName, PurdueEmail, AcademicUnit, ResponseId, GraduateStudent,
+            PersonalEmail, PhoneNumber, MailingList, KeyIssues
+        FROM Responses
+        WHERE StudentId IS NULL
+    )";
+
+#[derive(Template)]
+#[template(path = "responses.html")]
+pub struct ResponsesPage {
+    pub departments: Vec<DepartmentResponseRate>,
+    pub department_options: Vec<SelectOption>,
+    pub key_issue_options: Vec<SelectOption>,
+    pub status_options: Vec<SelectOption>,
+    pub mailing_list: bool,
+    pub contacts: Vec<Contact>,
+    pub contact_count: usize,
+    pub export_query: String,
+}
+
+pub struct SelectOption {
+    pub value: String,
+    pub selected: bool,
+}
+
+pub struct DepartmentResponseRate {
+    pub college: String,
+    pub department: String,
+    pub student_count: usize,
+    pub response_count: usize,
+    pub response_rate: String,
+}
+
+#[derive(Serialize)]
+pub struct Contact {
+    pub name: String,
+    pub email: String,
+    pub personal_email: String,
+    pub phone_number: String,
+    pub department: String,
+    pub office: String,
+    pub latest_compensation: String,
+    pub responded: String,
+    pub graduate_student: String,
+    pub mailing_list: String,
+    pub key_issues: String,
+}
+
+impl Contact {
+    fn fields(&self) -> [&str; EXPORT_HEADERS.len()] {
+        [
+            &self.name,
+            &self.email,
+            &self.personal_email,
+            &self.phone_number,
+            &self.department,
+            &self.office,
+            &self.latest_compensation,
+            &self.responded,
+            &self.graduate_student,
+            &self.mailing_list,
+            &self.key_issues,
+        ]
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct ResponsesQuery {
+    pub department: Option<String>,
+    pub key_issue: Option<String>,
+    pub mailing_list: Option<String>,
+    pub status: Option<String>,
+}
+
+// Shows response rates by department above the students and sign-ups matching the filters
+pub fn display_responses(
+    request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let query = parse_responses_query(request)?;
+    let connection = context.connection_pool.get()?;
+    let departments = fetch_response_rates(&connection)?;
+    let contact_count = count_contacts(&query, &connection)?;
+    let contacts = fetch_contacts(&query, Some(CONTACT_LIMIT), &connection)?;
+    let build_options = |values: Vec<String>, selected: &Option<String>| {
+        values
+            .into_iter()
+            .map(|value| SelectOption {
+                selected: selected.as_ref() == Some(&value),
+                value,
+            })
+            .collect()
+    };
+    let mut department_names: Vec<String> = departments
+        .iter()
+        .map(|department| department.department.clone())
+        .collect();
+    department_names.sort();
+    department_names.dedup();
+
+    Ok(Response::from_string(
+        ResponsesPage {
+            department_options: build_options(department_names, &query.department),
+            key_issue_options: build_options(
+                KEY_ISSUES.iter().map(|issue| issue.to_string()).collect(),
+                &query.key_issue,
+            ),
+            status_options: build_options(
+                vec![String::from("Responded"), String::from("Missing")],
+                &query.status,
+            ),
+            mailing_list: query.mailing_list.is_some(),
+            export_query: serde_qs::to_string(&query).map_err(|error| {
+                Status::Internal(anyhow!("Failed to serialize responses query: {}", error))
+            })?,
+            departments,
+            contacts,
+            contact_count,
+        }
+        .to_string(),
+    )
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
+}
+
+pub fn export_responses(
+    request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let query = parse_responses_query(request)?;
+    let connection = context.connection_pool.get()?;
+    let contacts = fetch_contacts(&query, None, &connection)?;
+
+    Ok(Response::from_data(write_contacts_csv(&contacts)?)
+        .with_header(Header::from_str("Content-Type: text/csv; charset=utf-8").unwrap())
+        .with_header(
+            Header::from_str("Content-Disposition: attachment; filename=\"contacts.csv\"").unwrap(),
+        ))
+}
+
+// Counts scraped students and linked responses for every department of every college
+pub fn fetch_response_rates(
+    connection: &Connection,
+) -> Result<Vec<DepartmentResponseRate>, Status> {
+    let mut statement = connection.prepare(
+        "SELECT COALESCE(College.Name, 'Unknown college') AS College, Students.Department,
+            COUNT(DISTINCT Students.Id) AS StudentCount,
+            COUNT(DISTINCT Responses.StudentId) AS ResponseCount
+        FROM Students
+        LEFT JOIN College
+        ON College.Id = Students.CollegeId
+        LEFT JOIN Responses
+        ON Responses.StudentId = Students.Id
+        GROUP BY College, Students.Department
+        ORDER BY College ASC, Students.Department ASC",
+    )?;
+    let rates = statement
+        .query_map([], |row| {
+            let student_count: usize = row.get("StudentCount")?;
+            let response_count: usize = row.get("ResponseCount")?;
+
+            Ok(DepartmentResponseRate {
+                college: row.get("College")?,
+                department: row.get("Department")?,
+                student_count,
+                response_count,
+                response_rate: format!(
+                    "{:.0}%",
+                    response_count as f64 * 100.0 / student_count.max(1) as f64
+                ),
+            })
+        })?
+        .collect::<Result<Vec<DepartmentResponseRate>, rusqlite::Error>>()?;
+
+    Ok(rates)
+}
+
+pub fn fetch_contacts(
+    query: &ResponsesQuery,
+    limit: Option<usize>,
+    connection: &Connection,
+) -> Result<Vec<Contact>, Status> {
+    let (condition, mut parameters) = build_contacts_condition(query)?;
+    let limit = match limit {
+        Some(limit) => {
+            parameters.push(Value::Integer(limit as i64));
+            format!("LIMIT ?{}", parameters.len())
+        }
+        None => String::new(),
+    };
+    let mut statement = connection.prepare(&format!(
+        "{CONTACTS}
+        SELECT Contacts.*,
+            (SELECT group_concat(Building || ' ' || Room, ', ') FROM Offices
+                WHERE Offices.StudentId = Contacts.StudentId) AS Office,
+            (SELECT AmountUsd FROM Salaries
+                WHERE Salaries.StudentId = Contacts.StudentId
+                ORDER BY Year DESC LIMIT 1) AS LatestAmountUsd
+        FROM Contacts
+        {condition}
+        ORDER BY Department ASC, Name ASC
+        {limit}"
+    ))?;
+    let contacts = statement
+        .query_map(params_from_iter(parameters), read_contact)?
+        .collect::<Result<Vec<Contact>, rusqlite::Error>>()?;
+
+    Ok(contacts)
+}
+
+fn count_contacts(query: &ResponsesQuery, connection: &Connection) -> Result<usize, Status> {
+    let (condition, parameters) = build_contacts_condition(query)?;
+    let count = connection.query_row(
+        &format!("{CONTACTS} SELECT COUNT(*) FROM Contacts {condition}"),
+        params_from_iter(parameters),
+        |row| row.get(0),
+    )?;
+
+    Ok(count)
+}
+
+// Key issues are stored as a JSON array, but responses written before the sign-up form may
+// hold free text, which never matches a key issue
+fn build_contacts_condition(query: &ResponsesQuery) -> Result<(String, Vec<Value>), Status> {
+    let mut conditions = vec![];
+    let mut parameters = vec![];
+
+    if let Some(department) = &query.department {
+        parameters.push(Value::Text(department.clone()));
+        conditions.push(format!("Department = ?{}", parameters.len()));
+    }
+
+    if let Some(key_issue) = &query.key_issue {
+        if !KEY_ISSUES.contains(&key_issue.as_str()) {
+            return Err(Status::InvalidArgument(anyhow!(
+                "Unknown key issue '{}'",
+                key_issue
+            )));
+        }
+
+        parameters.push(Value::Text(key_issue.clone()));
+        conditions.push(format!(
+            "CASE WHEN json_valid(KeyIssues) THEN EXISTS(
+                SELECT 1 FROM json_each(KeyIssues) WHERE json_each.value = ?{}
+            ) ELSE 0 END",
+            parameters.len()
+        ));
+    }
+
+    if query.mailing_list.is_some() {
+        conditions.push(String::from("MailingList = 'Yes'"));
+    }
+
+    match query.status.as_deref() {
+        Some("Responded") => conditions.push(String::from("ResponseId IS NOT NULL")),
+        Some("Missing") => conditions.push(String::from("ResponseId IS NULL")),
+        Some(status) => {
+            return Err(Status::InvalidArgument(anyhow!(
+                "Unknown response status '{}'",
+                status
+            )))
+        }
+        None => (),
+    }
+
+    if conditions.is_empty() {
+        return Ok((String::new(), parameters));
+    }
+
+    Ok((format!("WHERE {}", conditions.join(" AND ")), parameters))
+}
+
+fn read_contact(row: &Row) -> Result<Contact, rusqlite::Error> {
+    let text = |column: &str| {
+        row.get::<_, Option<String>>(column)
+            .map(Option::unwrap_or_default)
+    };
+    let responded = row.get::<_, Option<String>>("ResponseId")?.is_some();
+    let key_issues = text("KeyIssues")?;
+
+    Ok(Contact {
+        name: text("Name")?,
+        email: text("Email")?,
+        personal_email: text("PersonalEmail")?,
+        phone_number: text("PhoneNumber")?,
+        department: text("Department")?,
+        office: text("Office")?,
+        latest_compensation: row
+            .get::<_, Option<usize>>("LatestAmountUsd")?
+            .map(format_usd)
+            .unwrap_or_default(),
+        responded: String::from(if responded { "Yes" } else { "No" }),
+        graduate_student: match row.get::<_, Option<bool>>("GraduateStudent")? {
+            Some(true) => String::from("Yes"),
+            Some(false) => String::from("No"),
+            None => String::new(),
+        },
+        mailing_list: text("MailingList")?,
+        key_issues: serde_json::from_str::<Vec<String>>(&key_issues)
+            .map(|key_issues| key_issues.join(", "))
+            .unwrap_or(key_issues),
+    })
+}
+
+// Empty selects in the filter form mean no filter
+fn parse_responses_query(request: &Request) -> Result<ResponsesQuery, Status> {
+    let query: ResponsesQuery = extract_query(request.url()).map_err(|error| {
+        Status::InvalidArgument(anyhow!("Failed to parse responses query: {}", error))
+    })?;
+    let non_empty = |value: Option<String>| value.filter(|value| !value.trim().is_empty());
+
+    Ok(ResponsesQuery {
+        department: non_empty(query.department),
+        key_issue: non_empty(query.key_issue),
+        mailing_list: non_empty(query.mailing_list),
+        status: non_empty(query.status),
+    })
+}
+
+// Writes the contacts for spreadsheets. Most of the values come from the public sign-up form,
+// so cells a spreadsheet would run as a formula are quoted
+pub fn write_contacts_csv(contacts: &[Contact]) -> Result<Vec<u8>, Status> {
+    let write_csv = || {
+        let mut csv_writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(vec![]);
+        csv_writer.write_record(EXPORT_HEADERS)?;
+
+        for contact in contacts {
+            csv_writer.write_record(contact.fields().map(escape_formula))?;
+        }
+
+        csv_writer
+            .into_inner()
+            .map_err(|error| error.into_error().into())
+    };
+
+    write_csv().map_err(|error: csv::Error| {
+        Status::Internal(anyhow!("Failed to write contacts export: {}", error))
+    })
+}
@@ -1,101 +1,475 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::read_dir,
+    path::Path,
+};
+
+use anyhow::{anyhow, Error};
 use csv::Reader;
+use num_format::{Buffer, Locale};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Connection, Row};
 use serde::{Deserialize, Serialize};
 
-use crate::college::get_student_by_name;
+use crate::{
+    error::Status,
+    matching::{MatchCandidate, SalaryMatch, StudentIndex},
+};
 
-#[derive(Debug)]
+const COMPENSATION_HEADERS: [&str; 6] = [
+    "Year",
+    "Name",
+    "Department",
+    "JobTitle",
+    "City",
+    "TotalCompensation",
+];
+
+#[derive(Debug, Serialize)]
 pub struct Salary {
     pub student_id: String,
     pub amount_usd: usize,
     pub year: usize,
+    pub salary_name: String,
+    pub salary_department: String,
+    pub confidence: f64,
+    pub method: String,
+}
+
+// A salary row left without a student, either because nobody matched or because several
+// students matched about equally well
+#[derive(Debug)]
+pub struct UnresolvedSalary {
+    pub year: usize,
+    pub name: String,
+    pub department: String,
+    pub amount_usd: usize,
+    pub candidates: Vec<MatchCandidate>,
+}
+
+#[derive(Debug, Default)]
+pub struct ProcessedSalaries {
+    pub salaries: Vec<Salary>,
+    pub ambiguous: Vec<UnresolvedSalary>,
+    pub unmatched: Vec<UnresolvedSalary>,
+}
+
+// A reviewed salary row, identified by its year, name and department in the export
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SalaryKey {
+    pub year: usize,
+    pub name: String,
+    pub department: String,
+}
+
+pub struct SalaryMatchOverride {
+    pub student_id: Option<String>,
+    pub action: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IndianaCompensationRow {
     #[serde(rename = "Year")]
-    year: String,
+    pub year: String,
     #[serde(rename = "Name")]
-    name: String,
+    pub name: String,
     #[serde(rename = "Department")]
-    department: String,
+    pub department: String,
     #[serde(rename = "JobTitle")]
-    job_title: String,
+    pub job_title: String,
     #[serde(rename = "City")]
-    city: String,
+    pub city: String,
     #[serde(rename = "TotalCompensation")]
-    total_compensation: String,
+    pub total_compensation: String,
+}
+
+impl IndianaCompensationRow {
+    // The gateway labels the year as "Compensation in 2023" so the year is the last word
+    pub fn parse_year(&self) -> Option<usize> {
+        self.year.split_whitespace().last()?.parse().ok()
+    }
+
+    pub fn parse_amount_cents(&self) -> Option<usize> {
+        parse_usd_cents(&self.total_compensation)
+    }
+}
+
+// Parses amounts like "$31,250.5" into cents
+pub fn parse_usd_cents(amount: &str) -> Option<usize> {
+    let amount = amount.trim().replace(['$', ','], "");
+    let (dollars, cents) = amount.split_once('.').unwrap_or((&amount, "0"));
+
+    if dollars.is_empty() || cents.is_empty() || cents.len() > 2 {
+        return None;
+    }
+
+    let dollars: usize = dollars.parse().ok()?;
+    let cents: usize = format!("{:0<2}", cents).parse().ok()?;
+
+    Some(dollars * 100 + cents)
 }
 
+// Reads every yearly export in the salaries directory, or the single export it names,
+// skipping files that do not match the gateway format, and matches graduate students
+// against the scraped students
 pub fn process_salaries(
     connection_pool: &Pool<SqliteConnectionManager>,
-    data_path: &str,
-) -> Vec<Salary> {
-    let mut reader =
-        Reader::from_path(data_path).unwrap();
-    let mut salaries = vec![];
+    salaries_directory: &str,
+) -> ProcessedSalaries {
+    let student_index = StudentIndex::load(connection_pool);
+    let overrides = fetch_salary_match_overrides(connection_pool);
+    let mut processed = ProcessedSalaries::default();
+    let mut reviewed_salaries = vec![];
+    let mut matched_salaries = vec![];
+
+    for path in find_salary_files(salaries_directory) {
+        let rows = match read_compensation_rows(&path) {
+            Ok(rows) => rows,
+            Err(status) => {
+                eprintln!("Skipping {}: {}", path, status.message());
+                continue;
+            }
+        };
 
-    for row in reader.deserialize::<IndianaCompensationRow>() {
-        let row = row.unwrap();
+        for row in rows {
+            if row.job_title != "Graduate Student" {
+                continue;
+            }
 
-        if row.job_title != "Graduate Student" {
-            continue;
+            let (Some(year), Some(amount_usd)) = (row.parse_year(), row.parse_amount_cents())
+            else {
+                eprintln!("Skipping malformed salary row in {}: {:?}", path, row);
+                continue;
+            };
+
+            let key = SalaryKey {
+                year,
+                name: row.name.clone(),
+                department: row.department.clone(),
+            };
+
+            if let Some(salary_override) = overrides.get(&key) {
+                if let Some(student_id) = &salary_override.student_id {
+                    reviewed_salaries.push(Salary {
+                        student_id: student_id.clone(),
+                        amount_usd,
+                        year,
+                        salary_name: row.name,
+                        salary_department: row.department,
+                        confidence: 1.0,
+                        method: salary_override.action.clone(),
+                    });
+                }
+
+                continue;
+            }
+
+            match student_index.match_salary(&row.name, &row.department) {
+                SalaryMatch::Matched(candidate) => matched_salaries.push((
+                    Salary {
+                        student_id: candidate.student_id.clone(),
+                        amount_usd,
+                        year,
+                        salary_name: row.name,
+                        salary_department: row.department,
+                        confidence: candidate.confidence,
+                        method: candidate.method.as_str().to_string(),
+                    },
+                    candidate,
+                )),
+                SalaryMatch::Ambiguous(candidates) => processed.ambiguous.push(UnresolvedSalary {
+                    year,
+                    name: row.name,
+                    department: row.department,
+                    amount_usd,
+                    candidates,
+                }),
+                SalaryMatch::Unmatched => processed.unmatched.push(UnresolvedSalary {
+                    year,
+                    name: row.name,
+                    department: row.department,
+                    amount_usd,
+                    candidates: vec![],
+                }),
+            }
         }
+    }
 
-        let year: usize = row.year[16..].to_string().parse().unwrap();
-        let names: Vec<String> = row
-            .name
-            .rsplit(", ")
-            .map(|part| part.split(" "))
-            .flatten()
-            .map(|part| part.to_string())
-            .collect();
-        let amount_usd: usize = row
-            .total_compensation
-            .replace("$", "")
-            .replace(",", "")
-            .replace(".", "")
-            .parse()
-            .unwrap();
-        let student = get_student_by_name(&names, connection_pool);
+    // A student is paid once a year, so a row matched to a student another row already
+    // claimed for that year is left for review instead of replacing it. Reviewed rows claim
+    // their students before automatic matches do
+    let mut claimed: HashSet<(String, usize)> = HashSet::new();
+    let salaries = reviewed_salaries
+        .into_iter()
+        .map(|salary| (salary, None))
+        .chain(
+            matched_salaries
+                .into_iter()
+                .map(|(salary, candidate)| (salary, Some(candidate))),
+        );
 
-        if student.is_none() {
-            continue;
+    for (salary, candidate) in salaries {
+        if claimed.insert((salary.student_id.clone(), salary.year)) {
+            processed.salaries.push(salary);
+        } else {
+            processed.ambiguous.push(UnresolvedSalary {
+                year: salary.year,
+                name: salary.salary_name,
+                department: salary.salary_department,
+                amount_usd: salary.amount_usd,
+                candidates: candidate.into_iter().collect(),
+            });
         }
+    }
 
-        salaries.push(Salary {
-            student_id: student.unwrap().id,
-            amount_usd,
-            year,
-        })
+    processed
+}
+
+// Reads the rows of a single gateway export after checking it has the expected columns
+pub fn read_compensation_rows(path: &str) -> Result<Vec<IndianaCompensationRow>, Status> {
+    let mut reader = Reader::from_path(path).map_err(|error| {
+        Status::NotFound(Error::from(error).context(format!("Failed to open {}", path)))
+    })?;
+    let headers = reader.headers().map_err(|error| {
+        Status::InvalidArgument(Error::from(error).context(format!("Failed to read {}", path)))
+    })?;
+
+    for expected in COMPENSATION_HEADERS {
+        if !headers.iter().any(|header| header == expected) {
+            return Err(Status::InvalidArgument(anyhow!(
+                "{} is missing the {} column",
+                path,
+                expected
+            )));
+        }
+    }
+
+    let mut rows = vec![];
+
+    for (index, row) in reader.deserialize::<IndianaCompensationRow>().enumerate() {
+        match row {
+            Ok(row) => rows.push(row),
+            Err(error) => eprintln!("Skipping row {} of {}: {}", index + 1, path, error),
+        }
     }
 
-    salaries
+    Ok(rows)
 }
 
-pub fn store_salaries(salaries: &Vec<Salary>, connection_pool: &Pool<SqliteConnectionManager>) {
-    for salaries_chunk in salaries.chunks(50) {
-        let query = salaries_chunk
-            .iter()
-            .map(|salary| {
-                format!(
-                    "SELECT '{}' AS StudentId, {} AS Year, {} AS AmountUsd\n",
-                    salary.student_id, salary.year, salary.amount_usd
+fn find_salary_files(salaries_directory: &str) -> Vec<String> {
+    if Path::new(salaries_directory).is_file() {
+        return vec![String::from(salaries_directory)];
+    }
+
+    let entries = match read_dir(salaries_directory) {
+        Ok(entries) => entries,
+        Err(error) => {
+            eprintln!(
+                "Failed to read salaries directory {}: {}",
+                salaries_directory, error
+            );
+            return vec![];
+        }
+    };
+    let mut paths: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "csv"))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    paths.sort();
+
+    paths
+}
+
+pub fn store_salaries(
+    processed: &ProcessedSalaries,
+    connection_pool: &Pool<SqliteConnectionManager>,
+) {
+    let mut connection = connection_pool.get().unwrap();
+    let transaction = connection.transaction().unwrap();
+    // A salary row belongs to at most one student, so whatever it matched before goes before
+    // it is matched again or recorded as ambiguous or unmatched
+    let mut delete_match = transaction
+        .prepare(
+            "DELETE FROM Salaries
+            WHERE Year = ?1 AND SalaryName = ?2 AND SalaryDepartment = ?3",
+        )
+        .unwrap();
+
+    for salary in &processed.salaries {
+        delete_match
+            .execute(params![
+                salary.year,
+                salary.salary_name,
+                salary.salary_department
+            ])
+            .unwrap();
+        transaction
+            .execute(
+                "INSERT OR REPLACE INTO Salaries
+                (StudentId, Year, AmountUsd, SalaryName, SalaryDepartment, Confidence, Method)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    salary.student_id,
+                    salary.year,
+                    salary.amount_usd,
+                    salary.salary_name,
+                    salary.salary_department,
+                    salary.confidence,
+                    salary.method
+                ],
+            )
+            .unwrap();
+    }
+
+    transaction
+        .execute("DELETE FROM AmbiguousSalaryCandidates", [])
+        .unwrap();
+    transaction
+        .execute("DELETE FROM AmbiguousSalaries", [])
+        .unwrap();
+    transaction
+        .execute("DELETE FROM UnmatchedSalaries", [])
+        .unwrap();
+
+    for ambiguous in &processed.ambiguous {
+        delete_match
+            .execute(params![
+                ambiguous.year,
+                ambiguous.name,
+                ambiguous.department
+            ])
+            .unwrap();
+        transaction
+            .execute(
+                "INSERT OR REPLACE INTO AmbiguousSalaries (Year, Name, Department, AmountUsd)
+                VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    ambiguous.year,
+                    ambiguous.name,
+                    ambiguous.department,
+                    ambiguous.amount_usd
+                ],
+            )
+            .unwrap();
+
+        for candidate in &ambiguous.candidates {
+            transaction
+                .execute(
+                    "INSERT OR REPLACE INTO AmbiguousSalaryCandidates
+                    (Year, Name, Department, StudentId, Confidence, Method)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        ambiguous.year,
+                        ambiguous.name,
+                        ambiguous.department,
+                        candidate.student_id,
+                        candidate.confidence,
+                        candidate.method.as_str()
+                    ],
                 )
-            })
-            .collect::<Vec<String>>()
-            .join("UNION ALL ");
-        connection_pool
-            .get()
-            .unwrap()
+                .unwrap();
+        }
+    }
+
+    for unmatched in &processed.unmatched {
+        delete_match
+            .execute(params![
+                unmatched.year,
+                unmatched.name,
+                unmatched.department
+            ])
+            .unwrap();
+        transaction
             .execute(
-                &format!(
-                    "INSERT OR REPLACE INTO Salaries
-            (StudentId, Year, AmountUsd) {query}"
-                ),
-                [],
+                "INSERT OR REPLACE INTO UnmatchedSalaries (Year, Name, Department, AmountUsd)
+                VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    unmatched.year,
+                    unmatched.name,
+                    unmatched.department,
+                    unmatched.amount_usd
+                ],
             )
             .unwrap();
     }
+
+    drop(delete_match);
+    transaction.commit().unwrap();
+}
+
+fn fetch_salary_match_overrides(
+    connection_pool: &Pool<SqliteConnectionManager>,
+) -> HashMap<SalaryKey, SalaryMatchOverride> {
+    let connection = connection_pool.get().unwrap();
+    let mut statement = connection
+        .prepare("SELECT Year, Name, Department, StudentId, Action FROM SalaryMatchOverrides")
+        .unwrap();
+    let overrides = statement
+        .query_map([], |row| {
+            Ok((
+                SalaryKey {
+                    year: row.get("Year")?,
+                    name: row.get("Name")?,
+                    department: row.get("Department")?,
+                },
+                SalaryMatchOverride {
+                    student_id: row.get("StudentId")?,
+                    action: row.get("Action")?,
+                },
+            ))
+        })
+        .unwrap()
+        .flatten()
+        .collect();
+
+    overrides
+}
+
+// Reads a stored Salaries row, where salaries stored before matching have no match details
+pub fn read_salary(row: &Row) -> Result<Salary, rusqlite::Error> {
+    Ok(Salary {
+        student_id: row.get("StudentId")?,
+        amount_usd: row.get("AmountUsd")?,
+        year: row.get("Year")?,
+        salary_name: row
+            .get::<_, Option<String>>("SalaryName")?
+            .unwrap_or_default(),
+        salary_department: row
+            .get::<_, Option<String>>("SalaryDepartment")?
+            .unwrap_or_default(),
+        confidence: row.get::<_, Option<f64>>("Confidence")?.unwrap_or_default(),
+        method: row.get::<_, Option<String>>("Method")?.unwrap_or_default(),
+    })
+}
+
+// Formats an amount in cents like "$31,250.05"
+pub fn format_usd(amount_cents: usize) -> String {
+    let mut dollars = Buffer::default();
+    dollars.write_formatted(&(amount_cents / 100), &Locale::en);
+
+    format!("${}.{:02}", dollars, amount_cents % 100)
+}
+
+// Formats a signed change in cents like "+$1,250.00" or "-$310.05"
+pub fn format_usd_change(change_cents: i64) -> String {
+    let sign = if change_cents < 0 { '-' } else { '+' };
+
+    format!(
+        "{}{}",
+        sign,
+        format_usd(change_cents.unsigned_abs() as usize)
+    )
+}
+
+pub fn fetch_salary_years(connection: &Connection) -> Result<Vec<usize>, Status> {
+    let mut statement =
+        connection.prepare("SELECT DISTINCT Year FROM Salaries ORDER BY Year DESC")?;
+    let years = statement
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<usize>, rusqlite::Error>>()?;
+
+    Ok(years)
 }
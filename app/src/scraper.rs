@@ -9,8 +9,8 @@ use tokio::task::JoinSet;
 
 use crate::{
     college::{College, GraduateStudent},
-    error::Status,
-    html::{scrape_html, ScrapperSelectors},
+    error::{RowError, Status},
+    html::{scrape_html, DirectoryRow, ScrapperSelectors},
     parser::HtmlRowParser,
 };
 
@@ -56,6 +56,16 @@ pub struct ScrapperError {
     pub message: String,
 }
 
+pub struct ScrapedCollege {
+    pub college: College,
+    pub pages: Result<Vec<ScrapedPage>, Status>,
+}
+
+pub struct ScrapedPage {
+    pub number: usize,
+    pub students: Vec<Result<GraduateStudent, Status>>,
+}
+
 pub struct SinglePageStudentScrapper {
     pub client: Arc<Client>,
     pub college: College,
@@ -88,28 +98,63 @@ impl StudentScraper<(), String> for SinglePageStudentScrapper {
         Ok(
             scrape_html(&self.selector, &Html::parse_document(&response))?
                 .iter()
-                .filter_map(|row| {
-                    let Some(student) = self.parser.parse_row(row) else {
-                        return None;
-                    };
-
-                    Some(Ok(student))
-                })
+                .filter_map(|row| parse_directory_row(self.parser.as_ref(), row))
                 .collect(),
         )
     }
 }
 
-// TODO: Move onto scrapper impl this can then be overriden in liberal arts etc
+// Skips rows for other positions and reports rows that can't be parsed along with their html
+pub fn parse_directory_row(
+    parser: &dyn HtmlRowParser,
+    row: &DirectoryRow<'_>,
+) -> Option<Result<GraduateStudent, Status>> {
+    if !parser.is_valid_position(&row.position_element) {
+        return None;
+    }
+
+    // Students are keyed by the id in their email so a row without one can't be stored
+    Some(
+        parser
+            .parse_row(row)
+            .filter(|student| !student.id.trim().is_empty())
+            .ok_or_else(|| {
+                Status::InvalidArgument(Error::from(RowError::new(
+                    "Failed to parse student row",
+                    row.element
+                        .map(|element| element.html())
+                        .unwrap_or_default(),
+                )))
+            }),
+    )
+}
+
+// Scrapes every page of a college directory, keeping the college alongside the results
 pub async fn scrape_college<Request, Response>(
+    college: College,
+    scraper: Arc<impl StudentScraper<Request, Response> + Send + Sync + 'static>,
+) -> ScrapedCollege
+where
+    Response: PagedResponse + Debug + Serialize + Send + 'static,
+    Request: Serialize + PagedRequest + Debug + Default + Send + 'static,
+{
+    ScrapedCollege {
+        college,
+        pages: scrape_pages(scraper).await,
+    }
+}
+
+// TODO: Move onto scrapper impl this can then be overriden in liberal arts etc
+async fn scrape_pages<Request, Response>(
     scraper: Arc<impl StudentScraper<Request, Response> + Send + Sync + 'static>,
-) -> Result<Vec<Vec<Result<GraduateStudent, Status>>>, Status>
+) -> Result<Vec<ScrapedPage>, Status>
 where
     Response: PagedResponse + Debug + Serialize + Send + 'static,
     Request: Serialize + PagedRequest + Debug + Default + Send + 'static,
 {
     let initial_request = Request::default();
     let mut current_page = initial_request.current_page();
+    let initial_page = current_page;
     let initial_response = *scraper
         .deserialize(scraper.fetch(initial_request).await?)
         .await?;
@@ -121,7 +166,12 @@ where
     let initial_scraper = scraper.clone();
     current_page += 1;
 
-    active_scrapes.spawn(async move { initial_scraper.scrape(initial_response).await });
+    active_scrapes.spawn(async move {
+        Ok::<_, Status>((
+            initial_page,
+            initial_scraper.scrape(initial_response).await?,
+        ))
+    });
 
     while current_page < total_pages {
         let scraper = scraper.clone();
@@ -129,7 +179,7 @@ where
         active_requests.spawn(async move {
             let mut request = Request::default();
             request.set_page(current_page);
-            scraper.fetch(request).await
+            Ok::<_, Status>((current_page, scraper.fetch(request).await?))
         });
         current_page += 1;
     }
@@ -138,9 +188,9 @@ where
         let scraper = scraper.clone();
 
         active_serializations.spawn(async move {
-            scraper
-                .deserialize(http_response.map_err(|error| Status::Internal(Error::from(error)))??)
-                .await
+            let (page, http_response) =
+                http_response.map_err(|error| Status::Internal(Error::from(error)))??;
+            Ok::<_, Status>((page, scraper.deserialize(http_response).await?))
         });
     }
 
@@ -148,20 +198,20 @@ where
         let scraper = scraper.clone();
 
         active_scrapes.spawn(async move {
-            scraper
-                .scrape(*list_response.map_err(|error| Status::Internal(Error::from(error)))??)
-                .await
+            let (page, list_response) =
+                list_response.map_err(|error| Status::Internal(Error::from(error)))??;
+            Ok((page, scraper.scrape(*list_response).await?))
         });
     }
 
     while let Some(result) = active_scrapes.join_next().await {
-        let page = result.map_err(|error| Status::Internal(Error::from(error)))??;
+        let (number, students) = result.map_err(|error| Status::Internal(Error::from(error)))??;
 
-        if page.is_empty() {
+        if students.is_empty() {
             continue;
         }
 
-        paged_results.push(page);
+        paged_results.push(ScrapedPage { number, students });
     }
 
     Ok(paged_results)
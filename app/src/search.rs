@@ -0,0 +1,45 @@
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+
+// Weights for the bm25 ranking of StudentId, Name, Email, Department and Office
+pub const SEARCH_RANK: &str = "bm25(StudentSearch, 0.0, 4.0, 2.0, 1.0, 1.0)";
+
+// Turns free text into an FTS5 query where every word must prefix match, keeping only the
+// characters the tokenizer indexes so user input can't use the query syntax
+pub fn build_search_match(search: &str) -> Option<String> {
+    let terms: Vec<String> = search
+        .split(|character: char| !character.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"*", term))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+// Rebuilds the search index from the stored students and their offices
+pub fn store_student_search(connection_pool: &Pool<SqliteConnectionManager>) {
+    let mut connection = connection_pool.get().unwrap();
+    let transaction = connection.transaction().unwrap();
+
+    transaction
+        .execute("DELETE FROM StudentSearch", [])
+        .unwrap();
+    transaction
+        .execute(
+            "INSERT INTO StudentSearch (StudentId, Name, Email, Department, Office)
+            SELECT Students.Id, Students.Name, Students.Email, Students.Department,
+                group_concat(Offices.Building || ' ' || Offices.Room, ' ')
+            FROM Students
+            LEFT JOIN Offices
+            ON Students.Id = Offices.StudentId
+            GROUP BY Students.Id",
+            [],
+        )
+        .unwrap();
+
+    transaction.commit().unwrap();
+}
@@ -1,31 +1,111 @@
 use std::{
-    fs::{read_dir, File},
+    any::Any,
+    fs::File,
     io::{Cursor, Read},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
     str::FromStr,
-    sync::Arc,
-    thread,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
 };
 
+use anyhow::anyhow;
+use askama::Template;
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
 
 use crate::{
+    admin::display_pipeline_dashboard,
+    api::{json_error_response, route_api},
+    auth::{authorize, display_login, login, logout, redirect_response},
     college::display_college,
     configuration::Configuration,
     directory::{
         build_directory, build_directory_filter_menu, create_directory_filter,
-        delete_directory_filter, list_students, sort_directory,
+        delete_directory_filter, export_directory, list_students, page_directory, search_directory,
+        select_directory_year, sort_directory,
+    },
+    error::Status,
+    http::find_header,
+    match_review::{
+        confirm_salary_match, display_match_review, reassign_salary_match, reject_salary_match,
     },
+    member::display_member,
+    raises::{display_department_raises, display_student_compensation},
+    registry::CollegeSource,
+    responses::{display_responses, export_responses},
+    shutdown::Shutdown,
+    sign_up::{create_sign_up, display_sign_up},
+    statistics::display_statistics,
 };
 
+// How long a worker waits for a request before checking whether to shut down
+const RECEIVE_TIMEOUT: Duration = Duration::from_millis(250);
+// How often the supervisor checks for workers that died
+const SUPERVISOR_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Template)]
+#[template(path = "error.html")]
+pub struct ErrorFragment {
+    pub title: String,
+    pub message: String,
+}
+
+#[derive(Template)]
+#[template(path = "error_page.html")]
+pub struct ErrorPage {
+    pub error: ErrorFragment,
+}
+
+// Answers a request, rendering any error it fails with as the response
+pub type Router = fn(&mut Request, &Arc<ServerState>) -> Response<Box<dyn Read + Send>>;
+
 pub struct ServerState {
     pub connection_pool: Pool<SqliteConnectionManager>,
     pub configuration: Configuration,
+    pub sources: Vec<CollegeSource>,
+    // Worker threads currently serving requests
+    pub live_workers: AtomicUsize,
+}
+
+// Counts a worker as live for as long as its thread runs, including when it unwinds
+struct LiveWorker<'a>(&'a AtomicUsize);
+
+impl<'a> LiveWorker<'a> {
+    fn new(live_workers: &'a AtomicUsize) -> Self {
+        live_workers.fetch_add(1, Ordering::SeqCst);
+
+        LiveWorker(live_workers)
+    }
+}
+
+impl Drop for LiveWorker<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// Starts the configured number of workers under a supervisor that replaces any worker that
+// dies. Workers finish the request they are handling once a shutdown is requested and the
+// listener closes when the supervisor returns
+pub fn start_server(state: Arc<ServerState>, shutdown: Shutdown) -> JoinHandle<()> {
+    start_server_with_router(state, shutdown, route)
 }
 
-pub fn start_server(state: Arc<ServerState>) {
-    println!("Server is listening");
+// Starts the supervised workers with the given router in place of the application routes
+pub fn start_server_with_router(
+    state: Arc<ServerState>,
+    shutdown: Shutdown,
+    router: Router,
+) -> JoinHandle<()> {
+    let worker_count = state.configuration.workers;
+    assert!(worker_count > 0, "The server needs at least one worker");
+    println!("Server is listening with {} workers", worker_count);
     let server = Arc::new(
         Server::http(format!(
             "{}:{}",
@@ -33,24 +113,78 @@ pub fn start_server(state: Arc<ServerState>) {
         ))
         .unwrap(),
     );
-    let mut workers = Vec::with_capacity(4);
+    let mut workers: Vec<JoinHandle<()>> = (0..worker_count)
+        .map(|_| spawn_worker(server.clone(), state.clone(), shutdown.clone(), router))
+        .collect();
 
-    for _ in 0..workers.capacity() {
-        let server = server.clone();
-        let state = state.clone();
+    thread::spawn(move || {
+        while !shutdown.is_requested() {
+            thread::sleep(SUPERVISOR_INTERVAL);
 
-        workers.push(thread::spawn(move || loop {
-            match server.recv() {
-                Ok(mut request) => {
-                    let response = route(&mut request, &state);
-                    request.respond(response).unwrap();
+            for worker in workers.iter_mut() {
+                if !worker.is_finished() || shutdown.is_requested() {
+                    continue;
                 }
+
+                let replacement =
+                    spawn_worker(server.clone(), state.clone(), shutdown.clone(), router);
+
+                if std::mem::replace(worker, replacement).join().is_err() {
+                    eprintln!("A server worker panicked and was replaced");
+                }
+            }
+        }
+
+        for worker in workers {
+            if worker.join().is_err() {
+                eprintln!("A server worker panicked");
+            }
+        }
+    })
+}
+
+fn spawn_worker(
+    server: Arc<Server>,
+    state: Arc<ServerState>,
+    shutdown: Shutdown,
+    router: Router,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let _live_worker = LiveWorker::new(&state.live_workers);
+
+        while !shutdown.is_requested() {
+            match server.recv_timeout(RECEIVE_TIMEOUT) {
+                Ok(Some(mut request)) => {
+                    // A panicking handler fails its own request instead of the worker
+                    let response = catch_unwind(AssertUnwindSafe(|| router(&mut request, &state)))
+                        .unwrap_or_else(|payload| {
+                            let status = Status::Internal(anyhow!(
+                                "Handler panicked: {}",
+                                panic_message(payload.as_ref())
+                            ));
+
+                            render_error(&request, status).boxed()
+                        });
+
+                    if let Err(error) = request.respond(response) {
+                        eprintln!("Failed to respond: {}", error);
+                    }
+                }
+                Ok(None) => (),
                 Err(error) => {
                     eprintln!("error: {}", error)
                 }
             }
-        }));
-    }
+        }
+    })
+}
+
+pub fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
 }
 
 fn remove_query(url: &str) -> &str {
@@ -64,26 +198,83 @@ fn get_route_key(request: &Request) -> (&Method, &str) {
 // PERF NOTE: We are using dynamic dispatch it is slower with Box<dyn Read + Send>
 // can swap to an enum to wrap the type if this is a bottleneck
 fn route(request: &mut Request, state: &Arc<ServerState>) -> Response<Box<dyn Read + Send>> {
+    authorize(request, state)
+        .and_then(|_| dispatch(request, state))
+        .unwrap_or_else(|status| render_error(request, status).boxed())
+}
+
+fn dispatch(
+    request: &mut Request,
+    state: &Arc<ServerState>,
+) -> Result<Response<Box<dyn Read + Send>>, Status> {
     match get_route_key(request) {
-        (Method::Get, "/") => list_students(&request, &state).boxed(),
-        (Method::Get, "/college") => display_college(&request, &state).boxed(),
-        (Method::Get, "/directory") => build_directory(&request, &state).boxed(),
-        (Method::Delete, "/remove_directory_filter") => delete_directory_filter(request).boxed(),
-        (Method::Get, "/directory_filter_menu") => build_directory_filter_menu().boxed(),
-        (Method::Post, "/create_directory_filter") => create_directory_filter(request).boxed(),
-        (Method::Post, "/sort_directory") => sort_directory(request).boxed(),
-        (Method::Get, "/member") if request.url().starts_with("/member") => Response::from_string("epically in progress")
-            .with_status_code(StatusCode::from(200))
-            .boxed(),
-        (Method::Get, _) if request.url().starts_with("/assets") => serve_directory(
-            &request,
+        (Method::Get, "/") => list_students(request, state).map(Response::boxed),
+        (Method::Get, path) if path == "/api/v1" || path.starts_with("/api/v1/") => {
+            route_api(request, state).map(Response::boxed)
+        }
+        (Method::Get, "/admin/pipeline") => {
+            display_pipeline_dashboard(request, state).map(Response::boxed)
+        }
+        (Method::Get, "/admin/responses") => display_responses(request, state).map(Response::boxed),
+        (Method::Get, "/admin/responses/export") => {
+            export_responses(request, state).map(Response::boxed)
+        }
+        (Method::Get, "/login") => display_login(request).map(Response::boxed),
+        (Method::Post, "/login") => login(request, state).map(Response::boxed),
+        (Method::Post, "/logout") => logout(request, state).map(Response::boxed),
+        (Method::Get, "/admin/matches") => {
+            display_match_review(request, state).map(Response::boxed)
+        }
+        (Method::Post, "/admin/matches/confirm") => {
+            confirm_salary_match(request, state).map(Response::boxed)
+        }
+        (Method::Post, "/admin/matches/reassign") => {
+            reassign_salary_match(request, state).map(Response::boxed)
+        }
+        (Method::Post, "/admin/matches/reject") => {
+            reject_salary_match(request, state).map(Response::boxed)
+        }
+        (Method::Get, path) if path.starts_with("/college/") => {
+            display_college(request, state).map(Response::boxed)
+        }
+        (Method::Get, "/directory") => build_directory(request, state).map(Response::boxed),
+        (Method::Get, "/directory/export") => export_directory(request, state),
+        (Method::Delete, "/remove_directory_filter") => {
+            delete_directory_filter(request).map(Response::boxed)
+        }
+        (Method::Get, "/directory_filter_menu") => {
+            build_directory_filter_menu(request).map(Response::boxed)
+        }
+        (Method::Post, "/create_directory_filter") => {
+            create_directory_filter(request).map(Response::boxed)
+        }
+        (Method::Post, "/sort_directory") => sort_directory(request).map(Response::boxed),
+        (Method::Post, "/page_directory") => page_directory(request, state).map(Response::boxed),
+        (Method::Post, "/select_directory_year") => {
+            select_directory_year(request).map(Response::boxed)
+        }
+        (Method::Post, "/search_directory") => search_directory(request).map(Response::boxed),
+        (Method::Get, "/sign_up") => display_sign_up(request).map(Response::boxed),
+        (Method::Post, "/sign_up") => create_sign_up(request, state).map(Response::boxed),
+        (Method::Get, "/stats") => display_statistics(request, state).map(Response::boxed),
+        (Method::Get, "/stats/raises") => {
+            display_department_raises(request, state).map(Response::boxed)
+        }
+        (Method::Get, path) if path.starts_with("/students/") => {
+            display_student_compensation(request, state).map(Response::boxed)
+        }
+        (Method::Get, path) if path.starts_with("/member/") => {
+            display_member(request, state).map(Response::boxed)
+        }
+        (Method::Get, path) if path.starts_with("/assets/") => serve_directory(
+            request,
             "/assets",
             &state.configuration.files.assets_directory,
         )
-        .boxed(),
+        .map(Response::boxed),
         _ => {
             println!("Unhandled route {}", request.url());
-            Response::empty(StatusCode::from(404)).boxed()
+            Ok(Response::empty(StatusCode::from(404)).boxed())
         }
     }
 }
@@ -92,16 +283,88 @@ pub fn empty_fragment() -> Response<Cursor<Vec<u8>>> {
     Response::from_string("").with_header(Header::from_str("Content-Type: text/html").unwrap())
 }
 
-fn serve_directory(request: &Request, url: &str, directory_path: &str) -> Response<File> {
-    match read_dir(directory_path) {
-        Ok(directory) => directory
-            .filter_map(|file| file.ok())
-            .find(|file| {
-                file.path().to_str().unwrap().replace(&directory_path, "")
-                    == request.url().replace(&url, "")
-            })
-            .map(|file| Response::from_file(File::open(file.path()).unwrap()))
-            .unwrap(),
-        Err(_) => panic!("Can't find file {}", url),
+// Converts a handler error into a response the caller can show: JSON for the api, a
+// fragment for htmx requests and a full page otherwise. Internal errors are only logged and
+// pages that need a session send the user to sign in first
+pub fn render_error(request: &Request, status: Status) -> Response<Cursor<Vec<u8>>> {
+    if matches!(status, Status::Unauthenticated(_)) && !request.url().starts_with("/api/") {
+        let query = serde_urlencoded::to_string([("next", request.url())]).unwrap_or_default();
+
+        if let Ok(response) = redirect_response(request, &format!("/login?{}", query)) {
+            return response;
+        }
     }
+
+    let message = match status {
+        Status::Internal(_) => {
+            eprintln!("{} {} failed: {}", request.method(), request.url(), status);
+            String::from("Something went wrong, please try again later")
+        }
+        _ => status.message(),
+    };
+    let error = ErrorFragment {
+        title: match status {
+            Status::NotFound(_) => String::from("Not found"),
+            Status::InvalidArgument(_) => String::from("Invalid request"),
+            Status::Unauthenticated(_) => String::from("Sign in required"),
+            Status::PermissionDenied(_) => String::from("Not allowed"),
+            Status::Internal(_) => String::from("Server error"),
+        },
+        message,
+    };
+    let response = if request.url().starts_with("/api/") {
+        json_error_response(&status, error.message)
+    } else if find_header(request, "HX-Request").is_some() {
+        Response::from_string(error.to_string())
+            .with_header(Header::from_str("Content-Type: text/html").unwrap())
+            .with_header(Header::from_str("HX-Retarget: #errors").unwrap())
+            .with_header(Header::from_str("HX-Reswap: innerHTML").unwrap())
+    } else {
+        Response::from_string(ErrorPage { error }.to_string())
+            .with_header(Header::from_str("Content-Type: text/html").unwrap())
+    };
+
+    response.with_status_code(status_code(&status))
+}
+
+pub fn status_code(status: &Status) -> StatusCode {
+    StatusCode::from(match status {
+        Status::NotFound(_) => 404,
+        Status::InvalidArgument(_) => 400,
+        Status::Unauthenticated(_) => 401,
+        Status::PermissionDenied(_) => 403,
+        Status::Internal(_) => 500,
+    })
+}
+
+// Serves a file from the directory behind the url prefix, refusing paths that could reach
+// outside of it
+pub fn serve_directory(
+    request: &Request,
+    url: &str,
+    directory_path: &str,
+) -> Result<Response<File>, Status> {
+    let file_name = remove_query(request.url())
+        .strip_prefix(url)
+        .and_then(|path| path.strip_prefix('/'))
+        .unwrap_or_default();
+
+    if file_name.is_empty()
+        || file_name.contains('\\')
+        || file_name
+            .split('/')
+            .any(|part| part.is_empty() || part == "." || part == "..")
+    {
+        return Err(Status::NotFound(anyhow!("Unknown file {}", request.url())));
+    }
+
+    let path = Path::new(directory_path).join(file_name);
+
+    if !path.is_file() {
+        return Err(Status::NotFound(anyhow!("Unknown file {}", request.url())));
+    }
+
+    File::open(&path)
+        .map(Response::from_file)
+        .map_err(|error| Status::NotFound(anyhow!("Failed to open {}: {}", request.url(), error)))
 }
@@ -0,0 +1,50 @@
+use tokio::{
+    signal::{
+        ctrl_c,
+        unix::{signal, SignalKind},
+    },
+    sync::watch::{self, Sender},
+};
+
+// Tells the server workers and the pipeline to wind down. Cloning it hands out another
+// receiver so every task can wait on its own copy
+#[derive(Clone)]
+pub struct Shutdown {
+    receiver: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    pub fn new() -> (Sender<bool>, Shutdown) {
+        let (sender, receiver) = watch::channel(false);
+
+        (sender, Shutdown { receiver })
+    }
+
+    // Requests a shutdown once the process receives SIGINT or SIGTERM, which is how
+    // fly.io stops idle machines
+    pub fn listen_for_signals() -> Shutdown {
+        let (sender, shutdown) = Shutdown::new();
+        let mut terminate = signal(SignalKind::terminate()).unwrap();
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = ctrl_c() => (),
+                _ = terminate.recv() => (),
+            }
+            println!("Shutting down...");
+            let _ = sender.send(true);
+        });
+
+        shutdown
+    }
+
+    // Usable from the blocking server threads as well as from async tasks
+    pub fn is_requested(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    pub async fn requested(&mut self) {
+        // The sender only goes away when the signal listener stops, which also means stopping
+        let _ = self.receiver.wait_for(|requested| *requested).await;
+    }
+}
@@ -0,0 +1,230 @@
+use std::{io::Cursor, str::FromStr, sync::Arc};
+
+use anyhow::anyhow;
+use askama::Template;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use tiny_http::{Header, Request, Response};
+
+use crate::{
+    error::Status,
+    http::{parse_form_body, read_body},
+    id::generate_id,
+    pipeline::unix_timestamp,
+    server::ServerState,
+};
+
+pub const KEY_ISSUES: [&str; 8] = [
+    "Pay",
+    "Health care",
+    "Fees",
+    "Housing",
+    "International students",
+    "Workload",
+    "Harassment and discrimination",
+    "Childcare",
+];
+const MAX_FIELD_LENGTH: usize = 200;
+
+#[derive(Template)]
+#[template(path = "sign_up.html")]
+pub struct SignUpPage {
+    pub key_issues: [&'static str; 8],
+}
+
+#[derive(Template)]
+#[template(path = "sign_up_complete.html")]
+pub struct SignUpComplete {
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SignUpForm {
+    pub first_and_last_name: String,
+    pub purdue_email: String,
+    pub graduate_student: Option<String>,
+    pub academic_unit: String,
+    #[serde(default)]
+    pub personal_email: String,
+    #[serde(default)]
+    pub phone_number: String,
+    pub mailing_list: Option<String>,
+}
+
+// A validated sign-up ready to be stored as a Responses row
+#[derive(Debug, PartialEq, Eq)]
+pub struct SignUp {
+    pub first_and_last_name: String,
+    pub purdue_email: String,
+    pub graduate_student: bool,
+    pub academic_unit: String,
+    pub personal_email: Option<String>,
+    pub phone_number: Option<String>,
+    pub mailing_list: bool,
+    pub key_issues: Vec<String>,
+}
+
+impl SignUpForm {
+    // Trims and checks every field, keeping only key issues the form offers
+    pub fn validate(self, key_issues: Vec<String>) -> Result<SignUp, Status> {
+        let invalid = |message: &str| Status::InvalidArgument(anyhow!("{}", message));
+        let first_and_last_name = self.first_and_last_name.trim().to_string();
+        let purdue_email = self.purdue_email.trim().to_lowercase();
+        let academic_unit = self.academic_unit.trim().to_string();
+        let personal_email = Some(self.personal_email.trim().to_string())
+            .filter(|personal_email| !personal_email.is_empty());
+        let phone_number = Some(self.phone_number.trim().to_string())
+            .filter(|phone_number| !phone_number.is_empty());
+
+        if first_and_last_name.is_empty() || academic_unit.is_empty() {
+            return Err(invalid("Name and academic unit are required"));
+        }
+
+        if [&first_and_last_name, &purdue_email, &academic_unit]
+            .into_iter()
+            .chain(personal_email.as_ref())
+            .chain(phone_number.as_ref())
+            .any(|field| field.len() > MAX_FIELD_LENGTH)
+        {
+            return Err(invalid(&format!(
+                "Answers must be at most {} characters",
+                MAX_FIELD_LENGTH
+            )));
+        }
+
+        if !is_email(&purdue_email) || !purdue_email.ends_with("@purdue.edu") {
+            return Err(invalid("Enter your @purdue.edu email"));
+        }
+
+        if personal_email
+            .as_ref()
+            .is_some_and(|personal_email| !is_email(personal_email))
+        {
+            return Err(invalid("Enter a valid personal email"));
+        }
+
+        if phone_number.as_ref().is_some_and(|phone_number| {
+            let digits = phone_number.chars().filter(char::is_ascii_digit).count();
+
+            !(10..=15).contains(&digits)
+                || phone_number
+                    .chars()
+                    .any(|character| !character.is_ascii_digit() && !" +-().".contains(character))
+        }) {
+            return Err(invalid("Enter a phone number with 10 to 15 digits"));
+        }
+
+        let graduate_student = match self.graduate_student.as_deref() {
+            Some("yes") => true,
+            Some("no") => false,
+            _ => return Err(invalid("Tell us whether you are a graduate student")),
+        };
+
+        if let Some(key_issue) = key_issues
+            .iter()
+            .find(|key_issue| !KEY_ISSUES.contains(&key_issue.as_str()))
+        {
+            return Err(invalid(&format!("Unknown key issue '{}'", key_issue)));
+        }
+
+        Ok(SignUp {
+            first_and_last_name,
+            purdue_email,
+            graduate_student,
+            academic_unit,
+            personal_email,
+            phone_number,
+            mailing_list: self.mailing_list.is_some(),
+            key_issues: KEY_ISSUES
+                .iter()
+                .filter(|key_issue| key_issues.iter().any(|selected| selected == *key_issue))
+                .map(|key_issue| key_issue.to_string())
+                .collect(),
+        })
+    }
+}
+
+pub fn display_sign_up(_request: &Request) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    Ok(Response::from_string(
+        SignUpPage {
+            key_issues: KEY_ISSUES,
+        }
+        .to_string(),
+    )
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
+}
+
+// Stores the sign-up, linked to the scraped student with the same Purdue email. Each Purdue
+// email can only sign up once, but a repeated submission gets the same confirmation so the
+// form can't be used to find out who has signed up
+pub fn create_sign_up(
+    request: &mut Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let body = read_body(request)?;
+    let form: SignUpForm = parse_form_body(&body)?;
+    let key_issues = parse_form_body::<Vec<(String, String)>>(&body)?
+        .into_iter()
+        .filter(|(key, _)| key == "key_issue")
+        .map(|(_, key_issue)| key_issue)
+        .collect();
+    let sign_up = form.validate(key_issues)?;
+    let connection = context.connection_pool.get()?;
+
+    store_sign_up(&sign_up, &connection)?;
+
+    Ok(Response::from_string(
+        SignUpComplete {
+            name: sign_up.first_and_last_name,
+        }
+        .to_string(),
+    )
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
+}
+
+// Returns the new response id, or None when the Purdue email has already signed up
+pub fn store_sign_up(sign_up: &SignUp, connection: &Connection) -> Result<Option<String>, Status> {
+    let student_id: Option<String> = connection
+        .query_row(
+            "SELECT Id FROM Students WHERE lower(Email) = ?1",
+            [&sign_up.purdue_email],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let response_id = generate_id();
+    let key_issues = serde_json::to_string(&sign_up.key_issues)
+        .map_err(|error| Status::Internal(anyhow!("Failed to serialize key issues: {}", error)))?;
+    let inserted = connection.execute(
+        "INSERT INTO Responses
+        (ResponseId, Timestamp, StudentId, GraduateStudent, FirstAndLastName, AcademicUnit,
+            PurdueEmail, PersonalEmail, PhoneNumber, MailingList, KeyIssues)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        ON CONFLICT(PurdueEmail) DO NOTHING",
+        params![
+            response_id,
+            unix_timestamp(),
+            student_id,
+            sign_up.graduate_student,
+            sign_up.first_and_last_name,
+            sign_up.academic_unit,
+            sign_up.purdue_email,
+            sign_up.personal_email,
+            sign_up.phone_number,
+            if sign_up.mailing_list { "Yes" } else { "No" },
+            key_issues
+        ],
+    )?;
+
+    Ok((inserted > 0).then_some(response_id))
+}
+
+fn is_email(email: &str) -> bool {
+    email.split_once('@').is_some_and(|(local, domain)| {
+        !local.is_empty()
+            && domain.contains('.')
+            && !domain.starts_with('.')
+            && !domain.ends_with('.')
+            && !email.contains(char::is_whitespace)
+            && !domain.contains('@')
+    })
+}
@@ -0,0 +1,395 @@
+use std::{io::Cursor, str::FromStr, sync::Arc};
+
+use anyhow::anyhow;
+use askama::Template;
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+use tiny_http::{Header, Request, Response};
+
+use crate::{
+    directory::YearOption,
+    error::Status,
+    http::extract_query,
+    salary::{fetch_salary_years, format_usd, parse_usd_cents},
+    server::ServerState,
+};
+
+pub const HISTOGRAM_BINS: usize = 20;
+pub const HISTOGRAM_WIDTH: usize = 600;
+pub const HISTOGRAM_HEIGHT: usize = 120;
+const HISTOGRAM_BIN_ROUNDING: usize = 100_000;
+const SALARY_SOURCE: &str = "FROM Salaries
+    JOIN Students
+    ON Students.Id = Salaries.StudentId
+    LEFT JOIN College
+    ON College.Id = Students.CollegeId
+    WHERE ?1 IS NULL OR Salaries.Year = ?1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompensationSummary {
+    pub count: usize,
+    pub min: usize,
+    pub median: usize,
+    pub max: usize,
+}
+
+// Summarizes amounts sorted in ascending order, averaging the middle two amounts of an even
+// count for the median
+pub fn summarize_sorted(amounts: &[usize]) -> Option<CompensationSummary> {
+    let middle = amounts.len() / 2;
+    let median = if amounts.len().is_multiple_of(2) {
+        (amounts.get(middle.checked_sub(1)?)? + amounts.get(middle)?) / 2
+    } else {
+        *amounts.get(middle)?
+    };
+
+    Some(CompensationSummary {
+        count: amounts.len(),
+        min: *amounts.first()?,
+        median,
+        max: *amounts.last()?,
+    })
+}
+
+// Sorts the values in place, averaging the middle two values of an even count
+pub fn median(values: &mut [f64]) -> Option<f64> {
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+
+    if values.len().is_multiple_of(2) {
+        Some((values.get(middle.checked_sub(1)?)? + values.get(middle)?) / 2.0)
+    } else {
+        values.get(middle).copied()
+    }
+}
+
+#[derive(Template)]
+#[template(path = "statistics.html")]
+pub struct StatisticsPage {
+    pub years: Vec<YearOption>,
+    pub living_wage: String,
+    pub overall: Vec<Histogram>,
+    pub tables: Vec<StatisticsTable>,
+    pub college_histograms: Vec<Histogram>,
+}
+
+pub struct StatisticsTable {
+    pub title: String,
+    pub heading: String,
+    pub rows: Vec<StatisticsRow>,
+}
+
+pub struct StatisticsRow {
+    pub group: String,
+    pub count: usize,
+    pub mean: String,
+    pub lower_quartile: String,
+    pub median: String,
+    pub upper_quartile: String,
+    pub below_living_wage: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatisticsGrouping {
+    All,
+    College,
+    Department,
+    Year,
+}
+
+impl StatisticsGrouping {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            StatisticsGrouping::All => "'All salaries'",
+            StatisticsGrouping::College => "COALESCE(College.Name, 'Unknown college')",
+            StatisticsGrouping::Department => "Students.Department",
+            StatisticsGrouping::Year => "CAST(Salaries.Year AS TEXT)",
+        }
+    }
+}
+
+// Aggregates of the salaries in a group, with amounts in cents
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompensationStatistics {
+    pub group: String,
+    pub count: usize,
+    pub mean: f64,
+    pub lower_quartile: f64,
+    pub median: f64,
+    pub upper_quartile: f64,
+    pub below_living_wage: f64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Histogram {
+    pub title: String,
+    pub bars: Vec<HistogramBar>,
+    pub marker_x: Option<usize>,
+    pub marker_label: String,
+    pub max_label: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct HistogramBar {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub label: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct StatisticsQuery {
+    year: Option<String>,
+    living_wage: Option<String>,
+}
+
+// Shows pay aggregates for the selected year, or the latest one, broken down by college,
+// department and year next to histograms of the salaries
+pub fn display_statistics(
+    request: &Request,
+    context: &Arc<ServerState>,
+) -> Result<Response<Cursor<Vec<u8>>>, Status> {
+    let connection = context.connection_pool.get()?;
+    let query: StatisticsQuery = extract_query(request.url()).map_err(|error| {
+        Status::InvalidArgument(anyhow!("Failed to parse statistics query: {}", error))
+    })?;
+    let salary_years = fetch_salary_years(&connection)?;
+    let year = match query.year.as_deref().map(str::trim) {
+        None => salary_years.first().copied(),
+        Some("") => None,
+        Some(year) => Some(
+            year.parse()
+                .map_err(|_| Status::InvalidArgument(anyhow!("Unknown salary year '{}'", year)))?,
+        ),
+    };
+    let living_wage = match query.living_wage.as_deref().map(str::trim) {
+        None | Some("") => context.configuration.statistics.living_wage_usd * 100,
+        Some(living_wage) => parse_usd_cents(living_wage).ok_or_else(|| {
+            Status::InvalidArgument(anyhow!(
+                "The living wage expects a dollar amount, got '{}'",
+                living_wage
+            ))
+        })?,
+    };
+    let max_amount: Option<usize> = connection.query_row(
+        "SELECT MAX(AmountUsd) FROM Salaries WHERE ?1 IS NULL OR Year = ?1",
+        [year],
+        |row| row.get(0),
+    )?;
+    let bin_width = histogram_bin_width(max_amount.unwrap_or_default());
+    let build_table = |title: &str, heading: &str, grouping, year| {
+        Ok::<_, Status>(StatisticsTable {
+            title: title.to_string(),
+            heading: heading.to_string(),
+            rows: fetch_compensation_statistics(grouping, year, living_wage, &connection)?
+                .iter()
+                .map(format_statistics_row)
+                .collect(),
+        })
+    };
+    let tables = vec![
+        build_table("Overall", "Group", StatisticsGrouping::All, year)?,
+        build_table("By college", "College", StatisticsGrouping::College, year)?,
+        build_table(
+            "By department",
+            "Department",
+            StatisticsGrouping::Department,
+            year,
+        )?,
+        build_table("By year", "Year", StatisticsGrouping::Year, None)?,
+    ];
+    let build_histograms = |grouping| {
+        Ok::<_, Status>(
+            fetch_histogram_counts(grouping, year, bin_width, &connection)?
+                .into_iter()
+                .map(|(title, counts)| {
+                    build_histogram(
+                        title,
+                        &counts,
+                        bin_width,
+                        living_wage,
+                        format!("Living wage {}", format_usd(living_wage)),
+                    )
+                })
+                .collect::<Vec<Histogram>>(),
+        )
+    };
+
+    Ok(Response::from_string(
+        StatisticsPage {
+            years: salary_years
+                .into_iter()
+                .map(|salary_year| YearOption {
+                    year: salary_year,
+                    selected: year == Some(salary_year),
+                })
+                .collect(),
+            living_wage: format_usd(living_wage),
+            overall: build_histograms(StatisticsGrouping::All)?,
+            tables,
+            college_histograms: build_histograms(StatisticsGrouping::College)?,
+        }
+        .to_string(),
+    )
+    .with_header(Header::from_str("Content-Type: text/html").unwrap()))
+}
+
+// Computes the aggregates of each group in SQL. Quartiles are the midpoint of the two
+// salaries around the quartile position, which is the same as averaging the middle two
+// salaries for the median
+pub fn fetch_compensation_statistics(
+    grouping: StatisticsGrouping,
+    year: Option<usize>,
+    living_wage_cents: usize,
+    connection: &Connection,
+) -> Result<Vec<CompensationStatistics>, Status> {
+    let mut statement = connection.prepare(&format!(
+        "WITH Ranked AS (
+            SELECT {0} AS GroupName, AmountUsd,
+                ROW_NUMBER() OVER (PARTITION BY {0} ORDER BY AmountUsd) - 1 AS RowIndex,
+                COUNT(*) OVER (PARTITION BY {0}) AS GroupCount
+            {1}
+        )
+        SELECT GroupName, COUNT(*) AS Count, AVG(AmountUsd) AS Mean,
+            AVG(AmountUsd) FILTER (WHERE ABS(RowIndex - (GroupCount - 1) * 0.25) < 1)
+                AS LowerQuartile,
+            AVG(AmountUsd) FILTER (WHERE ABS(RowIndex - (GroupCount - 1) * 0.5) < 1)
+                AS Median,
+            AVG(AmountUsd) FILTER (WHERE ABS(RowIndex - (GroupCount - 1) * 0.75) < 1)
+                AS UpperQuartile,
+            AVG(AmountUsd < ?2) AS BelowLivingWage
+        FROM Ranked
+        GROUP BY GroupName
+        ORDER BY GroupName ASC",
+        grouping.as_sql(),
+        SALARY_SOURCE
+    ))?;
+    let statistics = statement
+        .query_map(params![year, living_wage_cents], |row| {
+            Ok(CompensationStatistics {
+                group: row.get("GroupName")?,
+                count: row.get("Count")?,
+                mean: row.get("Mean")?,
+                lower_quartile: row.get("LowerQuartile")?,
+                median: row.get("Median")?,
+                upper_quartile: row.get("UpperQuartile")?,
+                below_living_wage: row.get("BelowLivingWage")?,
+            })
+        })?
+        .collect::<Result<Vec<CompensationStatistics>, rusqlite::Error>>()?;
+
+    Ok(statistics)
+}
+
+// Counts the salaries of each group in HISTOGRAM_BINS bins of the given width, putting
+// anything past the last bin in the last bin
+pub fn fetch_histogram_counts(
+    grouping: StatisticsGrouping,
+    year: Option<usize>,
+    bin_width_cents: usize,
+    connection: &Connection,
+) -> Result<Vec<(String, Vec<usize>)>, Status> {
+    let mut statement = connection.prepare(&format!(
+        "SELECT {} AS GroupName, MIN(AmountUsd / ?2, ?3 - 1) AS Bin, COUNT(*) AS Count
+        {}
+        GROUP BY GroupName, Bin
+        ORDER BY GroupName ASC",
+        grouping.as_sql(),
+        SALARY_SOURCE
+    ))?;
+    let mut query = statement.query(params![year, bin_width_cents, HISTOGRAM_BINS])?;
+    let mut histograms: Vec<(String, Vec<usize>)> = vec![];
+
+    while let Some(row) = query.next()? {
+        let group: String = row.get("GroupName")?;
+        let bin: usize = row.get("Bin")?;
+
+        if histograms.last().map(|(last, _)| last) != Some(&group) {
+            histograms.push((group, vec![0; HISTOGRAM_BINS]));
+        }
+
+        if let Some((_, counts)) = histograms.last_mut() {
+            counts[bin] = row.get("Count")?;
+        }
+    }
+
+    Ok(histograms)
+}
+
+// Picks a bin width rounded up to whole thousands of dollars so the bins cover the largest
+// salary
+pub fn histogram_bin_width(max_amount_cents: usize) -> usize {
+    let minimum_width = (max_amount_cents + 1).div_ceil(HISTOGRAM_BINS);
+
+    minimum_width.div_ceil(HISTOGRAM_BIN_ROUNDING).max(1) * HISTOGRAM_BIN_ROUNDING
+}
+
+// Counts amounts into bins of the given width, putting anything past the last bin in the
+// last bin like fetch_histogram_counts
+pub fn count_histogram_bins(amounts: &[usize], bin_width_cents: usize) -> Vec<usize> {
+    let mut counts = vec![0; HISTOGRAM_BINS];
+
+    for amount in amounts {
+        counts[(amount / bin_width_cents).min(HISTOGRAM_BINS - 1)] += 1;
+    }
+
+    counts
+}
+
+// Lays out the bars of an SVG histogram, scaling them to the largest bin and marking an
+// amount such as the living wage when it falls inside the bins
+pub fn build_histogram(
+    title: String,
+    counts: &[usize],
+    bin_width_cents: usize,
+    marker_cents: usize,
+    marker_label: String,
+) -> Histogram {
+    let bar_width = HISTOGRAM_WIDTH / HISTOGRAM_BINS;
+    let max_count = counts.iter().copied().max().unwrap_or_default().max(1);
+    let range = bin_width_cents * counts.len();
+    let bars = counts
+        .iter()
+        .enumerate()
+        .map(|(index, count)| {
+            let height = count * HISTOGRAM_HEIGHT / max_count;
+
+            HistogramBar {
+                x: index * bar_width,
+                y: HISTOGRAM_HEIGHT - height,
+                width: bar_width - 2,
+                height,
+                label: format!(
+                    "{} to {}: {}",
+                    format_usd(index * bin_width_cents),
+                    format_usd((index + 1) * bin_width_cents),
+                    count
+                ),
+            }
+        })
+        .collect();
+
+    Histogram {
+        title,
+        bars,
+        marker_x: (marker_cents <= range && range > 0)
+            .then(|| marker_cents * bar_width * counts.len() / range),
+        marker_label,
+        max_label: format_usd(range),
+    }
+}
+
+fn format_statistics_row(statistics: &CompensationStatistics) -> StatisticsRow {
+    let format_amount = |amount: f64| format_usd(amount.round() as usize);
+
+    StatisticsRow {
+        group: statistics.group.clone(),
+        count: statistics.count,
+        mean: format_amount(statistics.mean),
+        lower_quartile: format_amount(statistics.lower_quartile),
+        median: format_amount(statistics.median),
+        upper_quartile: format_amount(statistics.upper_quartile),
+        below_living_wage: format!("{:.1}%", statistics.below_living_wage * 100.0),
+    }
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use std::{
+    io::Read,
+    sync::{atomic::AtomicUsize, Arc},
+};
+
+use perdue::{admin::display_pipeline_dashboard, registry::CollegeSource, server::ServerState};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tiny_http::{Request, StatusCode, TestRequest};
+
+fn build_source(id: &str, name: &str) -> CollegeSource {
+    serde_json::from_value(json!({
+        "id": id,
+        "name": name,
+        "base_url": "http://localhost",
+        "default_department": "",
+        "scraper": "LiberalArts"
+    }))
+    .unwrap()
+}
+
+// Science was scraped by two runs, the latest of which still hit one of the errors from the
+// first, while engineering has students but has never been scraped
+fn build_state() -> Arc<ServerState> {
+    let connection_pool = common::build_connection_pool();
+    common::seed_directory(&connection_pool);
+    connection_pool
+        .get()
+        .unwrap()
+        .execute_batch(
+            "INSERT INTO PipelineRuns (RunId, StartedAt, EndedAt, Status) VALUES
+                ('run-1', 86400, 86460, 'Failed'),
+                ('run-2', 172800, 172860, 'Succeeded');
+            INSERT INTO PipelineRunColleges
+                (RunId, CollegeId, Scraped, Errors, Inserted, Updated) VALUES
+                ('run-1', 'science', 1, 2, 1, 0),
+                ('run-2', 'science', 2, 1, 1, 0);
+            INSERT INTO ScrapeErrors (ErrorId, CollegeId, Page, Kind, Message, Snippet,
+                FirstRunId, LastRunId, Occurrences) VALUES
+                ('e1', 'science', 2, 'InvalidArgument', 'Missing email', '<tr>Cy</tr>',
+                    'run-1', 'run-2', 2),
+                ('e2', 'science', NULL, 'NotFound', 'Directory moved', '', 'run-1', 'run-1', 1);",
+        )
+        .unwrap();
+
+    Arc::new(ServerState {
+        connection_pool,
+        configuration: common::build_configuration(),
+        sources: vec![
+            build_source("science", "College of Science"),
+            build_source("engineering", "College of Engineering"),
+        ],
+        live_workers: AtomicUsize::new(2),
+    })
+}
+
+// The text of every table cell on the page, in order
+fn find_cells(body: &str) -> Vec<String> {
+    body.split("</td>")
+        .filter_map(|cell| cell.rsplit_once('>'))
+        .map(|(_, text)| text.trim().to_string())
+        .collect()
+}
+
+#[test]
+fn display_pipeline_dashboard_shows_the_latest_scrape_of_every_college() {
+    let state = build_state();
+    let request: Request = TestRequest::new().with_path("/admin/pipeline").into();
+    let response = match display_pipeline_dashboard(&request, &state) {
+        Ok(response) => response,
+        Err(status) => panic!("Dashboard should render: {}", status.message()),
+    };
+    let status_code = response.status_code();
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body).unwrap();
+    let cells = find_cells(&body);
+
+    assert_eq!(status_code, StatusCode(200));
+    assert!(body.contains("2 of 2 server workers running"));
+    assert_eq!(
+        cells[..6],
+        [
+            "1970-01-03 00:00:00",
+            "1970-01-03 00:01:00",
+            "Succeeded",
+            "1970-01-02 00:00:00",
+            "1970-01-02 00:01:00",
+            "Failed"
+        ]
+    );
+    assert_eq!(
+        cells[6..14],
+        [
+            "College of Science",
+            "1970-01-03 00:00:00",
+            "6",
+            "2",
+            "1",
+            "InvalidArgument",
+            "Page 2",
+            "Seen in 2 runs"
+        ]
+    );
+    assert!(body.contains("Missing email"));
+    assert!(body.contains("&lt;tr&gt;Cy&lt;/tr&gt;"));
+    assert!(!body.contains("Directory moved"));
+    assert_eq!(
+        cells[15..20],
+        ["College of Engineering", "Never", "1", "0", "0"]
+    );
+}
@@ -3,7 +3,7 @@ use std::{str::FromStr, sync::Arc};
 use mock_http::TestServer;
 use perdue::{
     agriculture::AgricultureScraper,
-    college::{GraduateStudent, Office},
+    college::{College, GraduateStudent, Office},
     error::Status,
     scraper::scrape_college,
 };
@@ -13,11 +13,12 @@ use tiny_http::{Header, Response};
 use tokio::test;
 
 async fn invoke_scrape_college(scraper: AgricultureScraper) -> Vec<Vec<GraduateStudent>> {
-    scrape_college(Arc::new(scraper))
+    scrape_college(College::default(), Arc::new(scraper))
         .await
+        .pages
         .expect("Should parse students")
         .into_iter()
-        .map(|x| x.into_iter().map(|y| y.unwrap()).collect())
+        .map(|x| x.students.into_iter().map(|y| y.unwrap()).collect())
         .collect()
 }
 
@@ -569,11 +570,18 @@ async fn scrape_agriculture_page_no_id_or_email() {
         base_url: server.url(),
     };
 
-    let students = scrape_college(Arc::new(scraper))
+    let students = scrape_college(College::default(), Arc::new(scraper))
         .await
+        .pages
         .expect("Should fail due to empty body");
 
-    assert!(matches!(students[0][0], Err(_)));
+    assert!(students[0].students[0].is_err());
+    assert_eq!(students[0].number, 0);
+    assert!(students[0].students[0]
+        .as_ref()
+        .unwrap_err()
+        .snippet()
+        .is_some_and(|snippet| snippet.contains("Agricultural Economics")));
 }
 
 #[test]
@@ -629,7 +637,9 @@ async fn scrape_agriculture_page_empty_json() {
         base_url: server.url(),
     };
 
-    let error = scrape_college(Arc::new(scraper)).await;
+    let error = scrape_college(College::default(), Arc::new(scraper))
+        .await
+        .pages;
 
     assert!(matches!(error, Err(Status::NotFound(_))));
 }
@@ -647,7 +657,9 @@ async fn scrape_agriculture_with_error() {
         base_url: server.url(),
     };
 
-    let error = scrape_college(Arc::new(scraper)).await;
+    let error = scrape_college(College::default(), Arc::new(scraper))
+        .await
+        .pages;
 
     assert!(matches!(error, Err(Status::Internal(_))));
 }
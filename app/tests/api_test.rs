@@ -0,0 +1,111 @@
+mod common;
+
+use std::{io::Read, sync::Arc};
+
+use perdue::{
+    api::route_api,
+    server::{render_error, ServerState},
+};
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use tiny_http::{Request, StatusCode, TestRequest};
+
+fn build_state() -> Arc<ServerState> {
+    let connection_pool = common::build_connection_pool();
+    common::seed_directory(&connection_pool);
+
+    common::build_state(connection_pool)
+}
+
+// Answers the request the way the server does, rendering errors as JSON
+fn get(path: &str, state: &Arc<ServerState>) -> (StatusCode, Value) {
+    let request: Request = TestRequest::new().with_path(path).into();
+    let response =
+        route_api(&request, state).unwrap_or_else(|status| render_error(&request, status));
+    let status_code = response.status_code();
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body).unwrap();
+
+    (status_code, serde_json::from_str(&body).unwrap())
+}
+
+#[test]
+fn api_routes_resources_and_reports_unknown_ones_as_json() {
+    let state = build_state();
+
+    assert_eq!(
+        get("/api/v1", &state),
+        (
+            StatusCode(200),
+            json!({ "data": ["/api/v1/students", "/api/v1/colleges", "/api/v1/departments"] })
+        )
+    );
+    assert_eq!(
+        get("/api/v1/colleges", &state),
+        (
+            StatusCode(200),
+            json!({ "data": [
+                { "id": "engineering", "name": "College of Engineering", "student_count": 1 },
+                { "id": "science", "name": "College of Science", "student_count": 6 }
+            ] })
+        )
+    );
+    assert_eq!(
+        get("/api/v1/students/nobody", &state),
+        (
+            StatusCode(404),
+            json!({ "kind": "NotFound", "message": "Unknown student 'nobody'" })
+        )
+    );
+    assert_eq!(get("/api/v1/teachers", &state).0, StatusCode(404));
+    assert_eq!(
+        get("/api/v1/students?page_size=x", &state).1["kind"],
+        "InvalidArgument"
+    );
+}
+
+#[test]
+fn api_reports_salaries_in_cents() {
+    let (status_code, body) = get("/api/v1/students/alee", &build_state());
+
+    assert_eq!(status_code, StatusCode(200));
+    assert_eq!(body["data"]["college_id"], "science");
+    assert_eq!(body["data"]["salaries"][1]["year"], 2023);
+    assert_eq!(body["data"]["salaries"][1]["amount_cents"], 3125050);
+    assert_eq!(body["data"]["salaries"][1]["amount_usd"], "$31,250.50");
+}
+
+#[test]
+fn api_pages_the_student_list() {
+    let (status_code, body) = get("/api/v1/students?page_size=3&page=2", &build_state());
+
+    assert_eq!(status_code, StatusCode(200));
+    assert_eq!(body["data"].as_array().unwrap().len(), 3);
+    assert_eq!(
+        body["pagination"],
+        json!({ "page": 2, "page_count": 3, "page_size": 3, "total": 7 })
+    );
+    assert_eq!(body["data"][0]["id"], "dlee");
+    assert_eq!(body["data"][0]["salaries"][0]["amount_cents"], 9000000);
+}
+
+#[test]
+fn api_lists_students_without_a_salary_and_filters_by_any_salary_year() {
+    let state = build_state();
+    let (_, body) = get("/api/v1/students?page=3&page_size=3", &state);
+
+    assert_eq!(body["data"][0]["id"], "jdoe2");
+    assert_eq!(body["data"][0]["college_id"], "science");
+    assert_eq!(body["data"][0]["salaries"], json!([]));
+
+    let (_, body) = get("/api/v1/students?year=2022", &state);
+    let ids: Vec<&str> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|student| student["id"].as_str().unwrap())
+        .collect();
+
+    assert_eq!(ids, ["alee", "bkim", "dlee"]);
+    assert_eq!(body["data"][0]["salaries"].as_array().unwrap().len(), 2);
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, SaltString},
+    Argon2, PasswordHasher,
+};
+use std::{
+    str::FromStr,
+    sync::{atomic::AtomicUsize, Arc},
+};
+
+use perdue::{
+    auth::{authorize, redirect_response, required_role, safe_redirect, verify_password, Role},
+    error::Status,
+    server::ServerState,
+};
+use pretty_assertions::assert_eq;
+use tiny_http::{Header, Method, Request, StatusCode, TestRequest};
+
+#[test]
+fn required_role_guards_admin_pages_by_role() {
+    assert_eq!(required_role(&Method::Get, "/directory"), None);
+    assert_eq!(required_role(&Method::Get, "/login"), None);
+    assert_eq!(
+        required_role(&Method::Get, "/admin/pipeline"),
+        Some(Role::Viewer)
+    );
+    assert_eq!(
+        required_role(&Method::Post, "/admin/pipeline"),
+        Some(Role::Admin)
+    );
+    assert_eq!(
+        required_role(&Method::Get, "/admin/responses/export"),
+        Some(Role::Organizer)
+    );
+    assert_eq!(
+        required_role(&Method::Post, "/admin/matches/confirm"),
+        Some(Role::Admin)
+    );
+}
+
+#[test]
+fn roles_parse_and_order_by_privilege() {
+    assert_eq!("Organizer".parse::<Role>().unwrap(), Role::Organizer);
+    assert!("owner".parse::<Role>().is_err());
+    assert!(Role::Viewer < Role::Organizer);
+    assert!(Role::Organizer < Role::Admin);
+}
+
+#[test]
+fn verify_password_checks_argon2_hashes() {
+    let salt = SaltString::generate(&mut OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(b"correct horse battery", &salt)
+        .unwrap()
+        .to_string();
+
+    assert!(verify_password("correct horse battery", &password_hash));
+    assert!(!verify_password("wrong horse battery", &password_hash));
+    assert!(!verify_password("correct horse battery", "not a hash"));
+}
+
+#[test]
+fn safe_redirect_only_allows_printable_paths_on_this_site() {
+    assert_eq!(
+        safe_redirect(Some("/admin/responses?status=Yes")),
+        "/admin/responses?status=Yes"
+    );
+    assert_eq!(safe_redirect(None), "/");
+    assert_eq!(safe_redirect(Some("https://evil.com")), "/");
+    assert_eq!(safe_redirect(Some("//evil.com")), "/");
+    assert_eq!(safe_redirect(Some("/\\evil.com")), "/");
+    assert_eq!(safe_redirect(Some("/\t/evil.com")), "/");
+    assert_eq!(safe_redirect(Some("/\r\nSet-Cookie: session=x")), "/");
+    assert_eq!(safe_redirect(Some("/é")), "/");
+}
+
+#[test]
+fn redirect_response_rejects_values_that_would_break_the_header() {
+    let request: Request = TestRequest::new().into();
+    let response = redirect_response(&request, "/directory").unwrap();
+
+    assert_eq!(response.status_code(), StatusCode(303));
+    assert!(response
+        .headers()
+        .iter()
+        .any(|header| header.field.equiv("Location") && header.value == "/directory"));
+    assert!(redirect_response(&request, "/\r\nSet-Cookie: session=x").is_err());
+    assert!(redirect_response(&request, "/é").is_err());
+
+    let htmx_request: Request = TestRequest::new()
+        .with_header(Header::from_str("HX-Request: true").unwrap())
+        .into();
+    let response = redirect_response(&htmx_request, "/directory").unwrap();
+
+    assert!(response
+        .headers()
+        .iter()
+        .any(|header| header.field.equiv("HX-Redirect") && header.value == "/directory"));
+}
+
+fn build_request(path: &str, authorization: Option<&str>) -> Request {
+    let request = TestRequest::new().with_path(path);
+
+    match authorization {
+        Some(credentials) => request
+            .with_header(
+                Header::from_str(&format!("Authorization: Basic {}", credentials)).unwrap(),
+            )
+            .into(),
+        None => request.into(),
+    }
+}
+
+fn build_state(shared_secret: &str) -> Arc<ServerState> {
+    let mut configuration = common::build_configuration();
+    configuration.organizer.shared_secret = String::from(shared_secret);
+
+    Arc::new(ServerState {
+        connection_pool: common::build_connection_pool(),
+        configuration,
+        sources: vec![],
+        live_workers: AtomicUsize::new(0),
+    })
+}
+
+#[test]
+fn authorize_accepts_the_organizer_shared_secret_on_organizer_pages_only() {
+    // organizer:s3cret and organizer:guess
+    let secret = "b3JnYW5pemVyOnMzY3JldA==";
+    let guess = "b3JnYW5pemVyOmd1ZXNz";
+    let state = build_state("s3cret");
+    let unauthenticated = |path: &str, authorization: Option<&str>, state: &Arc<ServerState>| {
+        matches!(
+            authorize(&build_request(path, authorization), state),
+            Err(Status::Unauthenticated(_))
+        )
+    };
+
+    assert!(authorize(&build_request("/admin/responses", Some(secret)), &state).is_ok());
+    assert!(authorize(
+        &build_request("/admin/responses/export", Some(secret)),
+        &state
+    )
+    .is_ok());
+    assert!(unauthenticated("/admin/responses", Some(guess), &state));
+    assert!(unauthenticated("/admin/responses", None, &state));
+    assert!(unauthenticated("/admin/matches", Some(secret), &state));
+    assert!(unauthenticated(
+        "/admin/responses",
+        Some(secret),
+        &build_state("")
+    ));
+}
@@ -0,0 +1,112 @@
+#![allow(dead_code)]
+
+use std::{
+    fs::{read_dir, read_to_string},
+    sync::{atomic::AtomicUsize, Arc},
+};
+
+use perdue::{configuration::Configuration, id::generate_id, server::ServerState};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use serde_json::{json, Value};
+
+// A pool on a private in-memory database with every migration applied. The database is
+// shared between the pooled connections and goes away with the pool
+pub fn build_connection_pool() -> Pool<SqliteConnectionManager> {
+    let manager = SqliteConnectionManager::file(format!(
+        "file:perdue-{}?mode=memory&cache=shared",
+        generate_id()
+    ));
+    let connection_pool = Pool::builder().max_size(4).build(manager).unwrap();
+    let mut migrations: Vec<(usize, String)> = read_dir("migrations")
+        .unwrap()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let id = name.strip_suffix("_up.sql")?.parse().ok()?;
+
+            Some((id, read_to_string(entry.path()).unwrap()))
+        })
+        .collect();
+    migrations.sort();
+    let connection = connection_pool.get().unwrap();
+
+    for (id, migration) in migrations {
+        connection
+            .execute_batch(&migration)
+            .unwrap_or_else(|error| panic!("Failed to apply migration {}: {}", id, error));
+    }
+
+    connection_pool
+}
+
+// Science has three physics students, two students who share a name and an unmatched
+// salary row that could belong to either, and a chemistry student alone in their
+// department. Engineering has one physics student who must not count towards science
+pub fn seed_directory(connection_pool: &Pool<SqliteConnectionManager>) {
+    connection_pool
+        .get()
+        .unwrap()
+        .execute_batch(
+            "INSERT INTO College (Id, Name) VALUES
+                ('science', 'College of Science'), ('engineering', 'College of Engineering');
+            INSERT INTO Students (Id, Name, Email, Department, CollegeId) VALUES
+                ('alee', 'Ann Lee', 'alee@purdue.edu', 'Physics', 'science'),
+                ('bkim', 'Bo Kim', 'bkim@purdue.edu', 'Physics', 'science'),
+                ('cpark', 'Cy Park', 'cpark@purdue.edu', 'Physics', 'science'),
+                ('egarcia', 'Eva Garcia', 'egarcia@purdue.edu', 'Chemistry', 'science'),
+                ('jdoe', 'Jane Doe', 'jdoe@purdue.edu', 'Mathematics', 'science'),
+                ('jdoe2', 'Jane Doe', 'jdoe2@purdue.edu', 'Mathematics', 'science'),
+                ('dlee', 'Di Lee', 'dlee@purdue.edu', 'Physics', 'engineering');
+            INSERT INTO Offices (OfficeId, StudentId, Building, Room) VALUES
+                ('o1', 'alee', 'PHYS', '120');
+            INSERT INTO Salaries (StudentId, Year, AmountUsd) VALUES
+                ('alee', 2022, 2500000), ('alee', 2023, 3125050),
+                ('bkim', 2022, 2000000), ('bkim', 2023, 2000000),
+                ('cpark', 2023, 1000000),
+                ('egarcia', 2023, 2800000),
+                ('dlee', 2022, 9000000), ('dlee', 2023, 9000000);
+            INSERT INTO UnmatchedSalaries (Year, Name, Department, AmountUsd) VALUES
+                (2023, 'Doe, Jane', 'WL - Mathematics', 3125000);",
+        )
+        .unwrap();
+}
+
+pub fn build_configuration() -> Configuration {
+    serde_json::from_value(build_configuration_json()).unwrap()
+}
+
+// The configuration file contents, for tests that leave a field out to check its default
+pub fn build_configuration_json() -> Value {
+    json!({
+        "database": {
+            "username": "",
+            "password": "",
+            "database_name": "perdue",
+            "connection_type": "Memory",
+            "connection_pool": { "max_size": 4 }
+        },
+        "files": {
+            "salaries_directory": "data/salaries",
+            "assets_directory": "assets",
+            "sources_path": "data/colleges.json"
+        },
+        "pipeline": { "interval_seconds": 3600 },
+        "statistics": {
+            "living_wage_usd": 35000,
+            "cpi": { "2022": 292.655, "2023": 304.702 }
+        },
+        "port": 0,
+        "host": "127.0.0.1",
+        "workers": 2
+    })
+}
+
+pub fn build_state(connection_pool: Pool<SqliteConnectionManager>) -> Arc<ServerState> {
+    Arc::new(ServerState {
+        connection_pool,
+        configuration: build_configuration(),
+        sources: vec![],
+        live_workers: AtomicUsize::new(0),
+    })
+}
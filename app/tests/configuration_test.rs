@@ -0,0 +1,32 @@
+use perdue::configuration::Configuration;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+#[test]
+fn configuration_written_before_the_pipeline_schedule_still_parses() {
+    let configuration: Configuration = serde_json::from_value(json!({
+        "database": {
+            "username": "",
+            "password": "",
+            "database_name": "perdue",
+            "connection_type": "Memory",
+            "connection_pool": { "max_size": 4 }
+        },
+        "files": {
+            "salaries_path": "data/salaries/salaries_2023.csv",
+            "assets_directory": "assets"
+        },
+        "port": 8080,
+        "host": "0.0.0.0"
+    }))
+    .unwrap();
+
+    assert_eq!(
+        configuration.files.salaries_directory,
+        "data/salaries/salaries_2023.csv"
+    );
+    assert_eq!(configuration.files.sources_path, "data/colleges.json");
+    assert_eq!(configuration.pipeline.interval_seconds, 24 * 60 * 60);
+    assert!(configuration.statistics.cpi.is_empty());
+    assert_eq!(configuration.workers, 4);
+}
@@ -0,0 +1,257 @@
+mod common;
+
+use std::{io::Read, str::FromStr, sync::Arc};
+
+use perdue::{
+    directory::{
+        build_directory, create_directory_filter, export_directory, sort_directory, Pagination,
+    },
+    error::Status,
+    server::{render_error, ServerState},
+};
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, StatusCode, TestRequest};
+
+#[test]
+fn pagination_clamps_requested_page() {
+    let pagination = Pagination::new(120, Some(7), 50);
+
+    assert_eq!(pagination.page, 3);
+    assert_eq!(pagination.page_count, 3);
+    assert_eq!(pagination.offset(), 100);
+    assert_eq!((pagination.first_row(), pagination.last_row()), (101, 120));
+    assert_eq!(pagination.previous_page(), Some(2));
+    assert_eq!(pagination.next_page(), None);
+    assert_eq!(Pagination::new(120, Some(0), 50).page, 1);
+}
+
+#[test]
+fn pagination_keeps_one_page_when_empty() {
+    let pagination = Pagination::new(0, None, 50);
+
+    assert_eq!(pagination.page_count, 1);
+    assert_eq!((pagination.first_row(), pagination.last_row()), (0, 0));
+    assert_eq!(pagination.previous_page(), None);
+    assert_eq!(pagination.next_page(), None);
+}
+
+fn build_state() -> Arc<ServerState> {
+    let connection_pool = common::build_connection_pool();
+    common::seed_directory(&connection_pool);
+
+    common::build_state(connection_pool)
+}
+
+// Exports the directory and reads the streamed body along with its content type
+fn export(path: &str, state: &Arc<ServerState>) -> (StatusCode, String, String) {
+    let request: Request = TestRequest::new().with_path(path).into();
+    let response = export_directory(&request, state).unwrap_or_else(|status| {
+        panic!("Export should start: {}", status.message());
+    });
+    let status_code = response.status_code();
+    let content_type = response
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Content-Type"))
+        .map(|header| header.value.to_string())
+        .unwrap_or_default();
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body).unwrap();
+
+    (status_code, content_type, body)
+}
+
+#[test]
+fn export_directory_streams_every_row_as_csv() {
+    let state = build_state();
+
+    let (status_code, content_type, body) = export("/directory/export", &state);
+
+    assert_eq!(status_code, StatusCode(200));
+    assert_eq!(content_type, "text/csv; charset=utf-8");
+    assert_eq!(
+        body,
+        "Id,Name,Email,Department,Building,Room,Year,CompensationCents,CompensationUsd
+alee,Ann Lee,alee@purdue.edu,Physics,PHYS,120,2022,2500000,\"$25,000.00\"
+alee,Ann Lee,alee@purdue.edu,Physics,PHYS,120,2023,3125050,\"$31,250.50\"
+bkim,Bo Kim,bkim@purdue.edu,Physics,,,2022,2000000,\"$20,000.00\"
+bkim,Bo Kim,bkim@purdue.edu,Physics,,,2023,2000000,\"$20,000.00\"
+cpark,Cy Park,cpark@purdue.edu,Physics,,,2023,1000000,\"$10,000.00\"
+dlee,Di Lee,dlee@purdue.edu,Physics,,,2022,9000000,\"$90,000.00\"
+dlee,Di Lee,dlee@purdue.edu,Physics,,,2023,9000000,\"$90,000.00\"
+egarcia,Eva Garcia,egarcia@purdue.edu,Chemistry,,,2023,2800000,\"$28,000.00\"
+"
+    );
+}
+
+#[test]
+fn export_directory_quotes_scraped_cells_that_spreadsheets_would_run() {
+    let state = build_state();
+    state
+        .connection_pool
+        .get()
+        .unwrap()
+        .execute_batch(
+            "UPDATE Students SET Name = '=HYPERLINK(\"http://evil.com\")', Department = '@SUM(A1)'
+                WHERE Id = 'egarcia';
+            UPDATE Offices SET Room = '-120' WHERE StudentId = 'alee';",
+        )
+        .unwrap();
+
+    let (_, _, csv) = export("/directory/export?year=2023&college_id=science", &state);
+    let (_, _, json) = export(
+        "/directory/export?format=json&year=2023&college_id=science",
+        &state,
+    );
+
+    assert!(csv.contains("alee,Ann Lee,alee@purdue.edu,Physics,PHYS,'-120,2023,"));
+    assert!(csv.contains(
+        r#"egarcia,"'=HYPERLINK(""http://evil.com"")",egarcia@purdue.edu,'@SUM(A1),,,2023,"#
+    ));
+    assert!(json.contains(r#""department":"@SUM(A1)""#));
+}
+
+#[test]
+fn export_directory_streams_sorted_rows_as_json() {
+    let state = build_state();
+
+    let (status_code, content_type, body) = export(
+        "/directory/export?format=json&year=2022&college_id=science&sort_column=AmountUsd&sort_direction=Ascending",
+        &state,
+    );
+
+    assert_eq!(status_code, StatusCode(200));
+    assert_eq!(content_type, "application/json");
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        json!([
+            {
+                "id": "bkim",
+                "name": "Bo Kim",
+                "email": "bkim@purdue.edu",
+                "department": "Physics",
+                "building": "",
+                "room": "",
+                "year": 2022,
+                "compensation_cents": 2000000,
+                "compensation_usd": "$20,000.00"
+            },
+            {
+                "id": "alee",
+                "name": "Ann Lee",
+                "email": "alee@purdue.edu",
+                "department": "Physics",
+                "building": "PHYS",
+                "room": "120",
+                "year": 2022,
+                "compensation_cents": 2500000,
+                "compensation_usd": "$25,000.00"
+            }
+        ])
+    );
+}
+
+#[test]
+fn export_directory_rejects_an_invalid_query_before_responding() {
+    let state = build_state();
+    let request: Request = TestRequest::new()
+        .with_path("/directory/export?sort_column=Password")
+        .into();
+
+    let error = match export_directory(&request, &state) {
+        Ok(_) => panic!("Export should not start"),
+        Err(status) => status,
+    };
+
+    assert_eq!(error.kind(), "InvalidArgument");
+}
+
+// A directory request the way htmx sends it, with the page url in HX-Current-Url
+fn build_htmx_request(method: Method, current_url: &str, body: &'static str) -> Request {
+    TestRequest::new()
+        .with_method(method)
+        .with_path("/directory")
+        .with_header(Header::from_str("HX-Request: true").unwrap())
+        .with_header(
+            Header::from_str(&format!(
+                "HX-Current-Url: http://localhost:8080{}",
+                current_url
+            ))
+            .unwrap(),
+        )
+        .with_body(body)
+        .into()
+}
+
+// The status code the server answers with, rendering errors the way the router does
+fn status_code_of<T>(request: &Request, result: Result<Response<T>, Status>) -> StatusCode
+where
+    T: Read,
+{
+    match result {
+        Ok(response) => response.status_code(),
+        Err(status) => render_error(request, status).status_code(),
+    }
+}
+
+#[test]
+fn directory_rejects_unknown_sort_and_filter_columns_with_400() {
+    let state = build_state();
+
+    for current_url in [
+        "/?sort_column=Password",
+        "/?sort_column=Id%3B%20DROP%20TABLE%20Students",
+        "/?sort_column=Id&sort_direction=Sideways",
+        "/?filters[0][column]=Password&filters[0][operator]=Equals&filters[0][value]=x",
+    ] {
+        let request = build_htmx_request(Method::Get, current_url, "");
+
+        assert_eq!(
+            status_code_of(&request, build_directory(&request, &state)),
+            StatusCode(400),
+            "{} should be rejected",
+            current_url
+        );
+    }
+
+    let mut sort_request = build_htmx_request(Method::Post, "/", "column=Password&state=Unsorted");
+    let sort = sort_directory(&mut sort_request);
+    assert_eq!(status_code_of(&sort_request, sort), StatusCode(400));
+
+    let mut filter_request =
+        build_htmx_request(Method::Post, "/", "column=Password&operator=Equals&value=x");
+    let filter = create_directory_filter(&mut filter_request);
+    assert_eq!(status_code_of(&filter_request, filter), StatusCode(400));
+}
+
+fn render_directory(current_url: &str, state: &Arc<ServerState>) -> String {
+    let request = build_htmx_request(Method::Get, current_url, "");
+    let response = match build_directory(&request, state) {
+        Ok(response) => response,
+        Err(status) => panic!("Directory should render: {}", status.message()),
+    };
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body).unwrap();
+
+    body
+}
+
+#[test]
+fn directory_binds_filter_values_instead_of_interpolating_them() {
+    let state = build_state();
+
+    let matching = render_directory(
+        "/?filters[0][column]=Name&filters[0][operator]=Equals&filters[0][value]=Ann%20Lee",
+        &state,
+    );
+    let injected = render_directory(
+        "/?filters[0][column]=Name&filters[0][operator]=Equals&filters[0][value]=x%27%20OR%20%271%27%3D%271",
+        &state,
+    );
+
+    assert!(matching.contains("Ann Lee"));
+    assert!(!matching.contains("Bo Kim"));
+    assert!(!injected.contains("Ann Lee"));
+    assert!(!injected.contains("Bo Kim"));
+}
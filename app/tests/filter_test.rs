@@ -0,0 +1,68 @@
+use perdue::{
+    directory::fetch_columns,
+    filter::{build_filter_condition, Filter, FilterOperator},
+};
+use pretty_assertions::assert_eq;
+use rusqlite::types::Value;
+
+fn filter(column: &str, operator: FilterOperator, value: &str, group: usize) -> Filter {
+    Filter {
+        column: column.to_string(),
+        operator,
+        value: value.to_string(),
+        upper_value: None,
+        group,
+    }
+}
+
+#[test]
+fn build_filter_condition_groups_with_and_and_or() {
+    let mut parameters = vec![];
+    let filters = vec![
+        filter("Department", FilterOperator::Contains, "Computer_", 0),
+        filter("AmountUsd", FilterOperator::LessThan, "$25,000", 0),
+        filter("Year", FilterOperator::Equals, "2023", 1),
+    ];
+
+    let condition = build_filter_condition(&filters, &fetch_columns(), &mut parameters).unwrap();
+
+    assert_eq!(
+        condition,
+        Some(String::from(
+            "(Department LIKE ?1 ESCAPE '\\' AND AmountUsd < ?2) OR (Year = ?3)"
+        ))
+    );
+    assert_eq!(
+        parameters,
+        vec![
+            Value::Text(String::from("%Computer\\_%")),
+            Value::Integer(2500000),
+            Value::Integer(2023)
+        ]
+    );
+}
+
+#[test]
+fn build_filter_condition_rejects_operators_that_do_not_fit_the_column() {
+    let mut parameters = vec![];
+    let filters = vec![filter("Year", FilterOperator::Contains, "20", 0)];
+
+    let error = build_filter_condition(&filters, &fetch_columns(), &mut parameters).unwrap_err();
+
+    assert_eq!(error.kind(), "InvalidArgument");
+}
+
+#[test]
+fn between_filter_requires_upper_value() {
+    let mut parameters = vec![];
+    let mut between = filter("AmountUsd", FilterOperator::Between, "20000", 0);
+
+    assert!(between.to_sql(&fetch_columns(), &mut parameters).is_err());
+
+    between.upper_value = Some(String::from("30000"));
+
+    assert_eq!(
+        between.to_sql(&fetch_columns(), &mut parameters).unwrap(),
+        "AmountUsd BETWEEN ?1 AND ?2"
+    );
+}
@@ -2,7 +2,7 @@ use std::{str::FromStr, sync::Arc};
 
 use mock_http::TestServer;
 use perdue::{
-    college::{GraduateStudent, Office},
+    college::{College, GraduateStudent, Office},
     error::Status,
     health::HealthScrapper,
     scraper::scrape_college,
@@ -12,11 +12,12 @@ use reqwest::Client;
 use tiny_http::{Header, Response};
 
 async fn invoke_scrape_college(scraper: Arc<HealthScrapper>) -> Vec<Vec<GraduateStudent>> {
-    scrape_college(scraper)
+    scrape_college(College::default(), scraper)
         .await
+        .pages
         .expect("Should parse students")
         .into_iter()
-        .map(|x| x.into_iter().map(|y| y.unwrap()).collect())
+        .map(|x| x.students.into_iter().map(|y| y.unwrap()).collect())
         .collect()
 }
 
@@ -56,8 +57,12 @@ async fn fetch_health_students_failed_fetch() {
     let server = TestServer::new();
     server.add_response(Response::from_string("").with_status_code(500));
 
-    let students =
-        scrape_college(HealthScrapper::new(&server.url(), Arc::new(Client::new()))).await;
+    let students = scrape_college(
+        College::default(),
+        HealthScrapper::new(&server.url(), Arc::new(Client::new())),
+    )
+    .await
+    .pages;
 
     assert!(matches!(students, Err(Status::Internal(_))))
 }
@@ -67,8 +72,12 @@ async fn fetch_health_students_invalid_json() {
     let server = TestServer::new();
     server.add_response(Response::from_data(vec![]));
 
-    let students =
-        scrape_college(HealthScrapper::new(&server.url(), Arc::new(Client::new()))).await;
+    let students = scrape_college(
+        College::default(),
+        HealthScrapper::new(&server.url(), Arc::new(Client::new())),
+    )
+    .await
+    .pages;
 
     assert!(matches!(students, Err(Status::InvalidArgument(_))))
 }
@@ -92,7 +101,12 @@ async fn fetch_health_students_no_html() {
     )
     .with_header(Header::from_str("Content-Type: text/html").unwrap()));
 
-    let error = scrape_college(HealthScrapper::new(&server.url(), Arc::new(Client::new()))).await;
+    let error = scrape_college(
+        College::default(),
+        HealthScrapper::new(&server.url(), Arc::new(Client::new())),
+    )
+    .await
+    .pages;
 
     assert!(matches!(error, Err(Status::NotFound(_))))
 }
@@ -132,7 +146,12 @@ async fn fetch_health_students_no_meta() {
         .with_header(Header::from_str("Content-Type: application/json").unwrap()),
     );
 
-    let error = scrape_college(HealthScrapper::new(&server.url(), Arc::new(Client::new()))).await;
+    let error = scrape_college(
+        College::default(),
+        HealthScrapper::new(&server.url(), Arc::new(Client::new())),
+    )
+    .await
+    .pages;
 
     assert!(matches!(error, Err(Status::NotFound(_))))
 }
@@ -151,11 +170,45 @@ async fn fetch_health_students_no_name() {
     )
     .with_header(Header::from_str("Content-Type: application/json").unwrap()));
 
-    let students = scrape_college(HealthScrapper::new(&server.url(), Arc::new(Client::new())))
-        .await
-        .unwrap();
+    let students = scrape_college(
+        College::default(),
+        HealthScrapper::new(&server.url(), Arc::new(Client::new())),
+    )
+    .await
+    .pages
+    .unwrap();
+
+    assert!(matches!(students[0].students[0], Err(Status::NotFound(_))))
+}
+
+#[tokio::test]
+async fn fetch_health_students_no_name_keeps_row_snippet() {
+    let server = TestServer::new();
+    server.add_response(Response::from_string(
+        r#"{
+            "html": "<tbody><tr class=\"faculty-table--row\"><td class=\"faculty-table--name\"></td><td class=\"faculty-table--department\">School of Health Sciences</td></tr></tbody>",
+            "meta": {
+                "totalposts": 1,
+                "postcount": 1
+            }
+        }"#
+    )
+    .with_header(Header::from_str("Content-Type: application/json").unwrap()));
 
-    assert!(matches!(students[0][0], Err(Status::NotFound(_))))
+    let students = scrape_college(
+        College::default(),
+        HealthScrapper::new(&server.url(), Arc::new(Client::new())),
+    )
+    .await
+    .pages
+    .unwrap();
+    let error = students[0].students[0].as_ref().unwrap_err();
+
+    assert_eq!(error.kind(), "NotFound");
+    assert_eq!(error.message(), "Name link element not found");
+    assert!(error
+        .snippet()
+        .is_some_and(|snippet| snippet.contains("School of Health Sciences")));
 }
 
 #[tokio::test]
@@ -172,11 +225,15 @@ async fn fetch_health_students_no_name_text() {
     )
     .with_header(Header::from_str("Content-Type: application/json").unwrap()));
 
-    let students = scrape_college(HealthScrapper::new(&server.url(), Arc::new(Client::new())))
-        .await
-        .unwrap();
+    let students = scrape_college(
+        College::default(),
+        HealthScrapper::new(&server.url(), Arc::new(Client::new())),
+    )
+    .await
+    .pages
+    .unwrap();
 
-    assert!(matches!(students[0][0], Err(Status::NotFound(_))))
+    assert!(matches!(students[0].students[0], Err(Status::NotFound(_))))
 }
 
 #[tokio::test]
@@ -193,11 +250,15 @@ async fn fetch_health_students_no_name_link() {
     )
     .with_header(Header::from_str("Content-Type: application/json").unwrap()));
 
-    let students = scrape_college(HealthScrapper::new(&server.url(), Arc::new(Client::new())))
-        .await
-        .unwrap();
+    let students = scrape_college(
+        College::default(),
+        HealthScrapper::new(&server.url(), Arc::new(Client::new())),
+    )
+    .await
+    .pages
+    .unwrap();
 
-    assert!(matches!(students[0][0], Err(Status::NotFound(_))))
+    assert!(matches!(students[0].students[0], Err(Status::NotFound(_))))
 }
 
 #[tokio::test]
@@ -214,11 +275,15 @@ async fn fetch_health_students_no_department() {
     )
     .with_header(Header::from_str("Content-Type: application/json").unwrap()));
 
-    let students = scrape_college(HealthScrapper::new(&server.url(), Arc::new(Client::new())))
-        .await
-        .unwrap();
+    let students = scrape_college(
+        College::default(),
+        HealthScrapper::new(&server.url(), Arc::new(Client::new())),
+    )
+    .await
+    .pages
+    .unwrap();
 
-    assert!(matches!(students[0][0], Err(Status::NotFound(_))))
+    assert!(matches!(students[0].students[0], Err(Status::NotFound(_))))
 }
 
 #[tokio::test]
@@ -268,11 +333,15 @@ async fn fetch_health_students_fails_with_no_email() {
     .with_header(Header::from_str("Content-Type: application/json").unwrap()));
     server.add_response(Response::from_string("<html><body></body></html>"));
 
-    let students = scrape_college(HealthScrapper::new(&server.url(), Arc::new(Client::new())))
-        .await
-        .unwrap();
+    let students = scrape_college(
+        College::default(),
+        HealthScrapper::new(&server.url(), Arc::new(Client::new())),
+    )
+    .await
+    .pages
+    .unwrap();
 
-    assert!(matches!(students[0][0], Err(Status::NotFound(_))))
+    assert!(matches!(students[0].students[0], Err(Status::NotFound(_))))
 }
 
 #[tokio::test]
@@ -292,9 +361,13 @@ async fn fetch_health_students_no_email() {
         "<html><body><div class=\"email\"><a></a></div></body></html>",
     ));
 
-    let students = scrape_college(HealthScrapper::new(&server.url(), Arc::new(Client::new())))
-        .await
-        .unwrap();
+    let students = scrape_college(
+        College::default(),
+        HealthScrapper::new(&server.url(), Arc::new(Client::new())),
+    )
+    .await
+    .pages
+    .unwrap();
 
-    assert!(matches!(students[0][0], Err(Status::InvalidArgument(_))))
+    assert!(matches!(students[0].students[0], Err(Status::InvalidArgument(_))))
 }
@@ -0,0 +1,241 @@
+mod common;
+
+use common::build_connection_pool;
+use perdue::{
+    college::{College, GraduateStudent},
+    error::Status,
+    history::{fetch_department_changes, mark_absent_students, record_student_history},
+};
+use pretty_assertions::assert_eq;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+
+fn build_college() -> College {
+    College {
+        id: String::from("science"),
+        name: String::from("Science"),
+        ..College::default()
+    }
+}
+
+fn build_student(id: &str, department: &str) -> Result<GraduateStudent, Status> {
+    Ok(GraduateStudent {
+        names: vec![String::from("Jane"), String::from(id)],
+        id: String::from(id),
+        email: format!("{}@purdue.edu", id),
+        department: String::from(department),
+        ..GraduateStudent::default()
+    })
+}
+
+// (Version, Department, ChangedFields, LastSeenRunId, AbsentSince) for every version of a student
+fn fetch_versions(
+    student_id: &str,
+    connection_pool: &Pool<SqliteConnectionManager>,
+) -> Vec<(usize, String, String, String, Option<u64>)> {
+    let connection = connection_pool.get().unwrap();
+    let mut statement = connection
+        .prepare(
+            "SELECT Version, Department, ChangedFields, LastSeenRunId, AbsentSince
+            FROM StudentHistory WHERE StudentId = ?1 ORDER BY Version",
+        )
+        .unwrap();
+
+    statement
+        .query_map([student_id], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+            ))
+        })
+        .unwrap()
+        .map(|row| row.unwrap())
+        .collect()
+}
+
+#[test]
+fn record_student_history_versions_only_changed_students() {
+    let connection_pool = build_connection_pool();
+    let college = build_college();
+
+    record_student_history(
+        "run-1",
+        100,
+        &college,
+        &[build_student("jdoe", "Physics")],
+        &connection_pool,
+    );
+    record_student_history(
+        "run-2",
+        200,
+        &college,
+        &[build_student("jdoe", "Physics")],
+        &connection_pool,
+    );
+
+    assert_eq!(
+        fetch_versions("jdoe", &connection_pool),
+        vec![(1, "Physics".into(), "".into(), "run-2".into(), None)]
+    );
+
+    record_student_history(
+        "run-3",
+        300,
+        &college,
+        &[build_student("jdoe", "Chemistry")],
+        &connection_pool,
+    );
+
+    assert_eq!(
+        fetch_versions("jdoe", &connection_pool),
+        vec![
+            (1, "Physics".into(), "".into(), "run-2".into(), None),
+            (
+                2,
+                "Chemistry".into(),
+                "Department".into(),
+                "run-3".into(),
+                None
+            ),
+        ]
+    );
+}
+
+#[test]
+fn mark_absent_students_marks_unseen_students_until_they_return() {
+    let connection_pool = build_connection_pool();
+    let college = build_college();
+
+    record_student_history(
+        "run-1",
+        100,
+        &college,
+        &[
+            build_student("jdoe", "Physics"),
+            build_student("rroe", "Physics"),
+        ],
+        &connection_pool,
+    );
+    record_student_history(
+        "run-2",
+        200,
+        &college,
+        &[build_student("jdoe", "Physics")],
+        &connection_pool,
+    );
+
+    assert_eq!(
+        mark_absent_students("run-2", 200, &college, &connection_pool),
+        1
+    );
+    assert_eq!(
+        mark_absent_students("run-2", 200, &college, &connection_pool),
+        0
+    );
+    assert_eq!(
+        fetch_versions("rroe", &connection_pool),
+        vec![(1, "Physics".into(), "".into(), "run-1".into(), Some(200))]
+    );
+    assert_eq!(
+        fetch_versions("jdoe", &connection_pool),
+        vec![(1, "Physics".into(), "".into(), "run-2".into(), None)]
+    );
+
+    record_student_history(
+        "run-3",
+        300,
+        &college,
+        &[
+            build_student("jdoe", "Physics"),
+            build_student("rroe", "Physics"),
+        ],
+        &connection_pool,
+    );
+
+    assert_eq!(
+        mark_absent_students("run-3", 300, &college, &connection_pool),
+        0
+    );
+    assert_eq!(
+        fetch_versions("rroe", &connection_pool),
+        vec![
+            (1, "Physics".into(), "".into(), "run-1".into(), Some(200)),
+            (2, "Physics".into(), "Returned".into(), "run-3".into(), None),
+        ]
+    );
+}
+
+#[test]
+fn fetch_department_changes_lists_students_who_joined_or_left() {
+    let connection_pool = build_connection_pool();
+    let college = build_college();
+
+    record_student_history(
+        "run-1",
+        0,
+        &college,
+        &[
+            build_student("jdoe", "Physics"),
+            build_student("rroe", "Physics"),
+        ],
+        &connection_pool,
+    );
+    record_student_history(
+        "run-2",
+        86400,
+        &college,
+        &[
+            build_student("jdoe", "Chemistry"),
+            build_student("asmith", "Physics"),
+        ],
+        &connection_pool,
+    );
+    mark_absent_students("run-2", 86400, &college, &connection_pool);
+
+    let changes: Vec<(String, String, String, String)> =
+        fetch_department_changes(&connection_pool.get().unwrap(), 10)
+            .unwrap()
+            .into_iter()
+            .map(|change| {
+                (
+                    change.changed_at,
+                    change.name,
+                    change.department,
+                    change.change,
+                )
+            })
+            .collect();
+
+    assert_eq!(
+        changes,
+        vec![
+            (
+                "1970-01-02 00:00:00".into(),
+                "Jane asmith".into(),
+                "Physics".into(),
+                "Joined".into()
+            ),
+            (
+                "1970-01-02 00:00:00".into(),
+                "Jane jdoe".into(),
+                "Chemistry".into(),
+                "Joined".into()
+            ),
+            (
+                "1970-01-02 00:00:00".into(),
+                "Jane jdoe".into(),
+                "Physics".into(),
+                "Left".into()
+            ),
+            (
+                "1970-01-02 00:00:00".into(),
+                "Jane rroe".into(),
+                "Physics".into(),
+                "Left".into()
+            ),
+        ]
+    );
+}
@@ -1,17 +1,18 @@
 use std::sync::Arc;
 
 use mock_http::TestServer;
-use perdue::{college::{GraduateStudent, Office}, liberal_arts::LiberalArtsScrapper, scraper::scrape_college};
+use perdue::{college::{College, GraduateStudent, Office}, liberal_arts::LiberalArtsScrapper, scraper::scrape_college};
 use pretty_assertions::assert_eq;
 use reqwest::Client;
 use tiny_http::Response;
 
 async fn invoke_scrape_college(scraper: Arc<LiberalArtsScrapper>) -> Vec<Vec<GraduateStudent>> {
-    scrape_college(scraper)
+    scrape_college(College::default(), scraper)
         .await
+        .pages
         .expect("Should parse students")
         .into_iter()
-        .map(|x| x.into_iter().map(|y| y.unwrap()).collect())
+        .map(|x| x.students.into_iter().map(|y| y.unwrap()).collect())
         .collect()
 }
 
@@ -50,3 +51,45 @@ async fn should_fetch_liberal_arts_student() {
 
     assert_eq!(students, expected_students);
 }
+
+#[tokio::test]
+async fn should_report_unparseable_rows_and_skip_other_positions() {
+    let server = TestServer::new();
+    server.add_response(Response::from_string(
+        r#"
+        <!DOCTYPE html>
+        <html>
+            <body>
+                <table>
+                    <tbody>
+                        <tr class="profile-row">
+                            <td><a href="profiles/jo-missing.html">Jo Missing</a></td>
+                            <td>Graduate Student // History</td>
+                            <td>&nbsp;</td>
+                            <td>&nbsp;</td>
+                            <td>&nbsp;</td>
+                        </tr>
+                        <tr class="profile-row">
+                            <td><a href="profiles/pat-professor.html">Pat Professor</a></td>
+                            <td>Professor // History</td>
+                            <td>&nbsp;</td>
+                            <td>pprofessor@purdue.edu</td>
+                            <td>&nbsp;</td>
+                        </tr>
+                    </tbody>
+                </table>
+            </body>
+        </html>"#,
+    ));
+    let scraper = LiberalArtsScrapper::new(&server.url(), Arc::new(Client::new()));
+
+    let pages = scrape_college(College::default(), scraper)
+        .await
+        .pages
+        .expect("Should parse the page");
+
+    assert_eq!(pages[0].students.len(), 1);
+    let error = pages[0].students[0].as_ref().unwrap_err();
+    assert_eq!(error.message(), "Failed to parse student row");
+    assert!(error.snippet().unwrap().contains("Jo Missing"));
+}
@@ -0,0 +1,255 @@
+mod common;
+
+use std::{
+    env::temp_dir,
+    fs::{create_dir_all, write},
+    io::Read,
+    sync::Arc,
+};
+
+use perdue::{
+    id::generate_id,
+    match_review::{
+        confirm_salary_match, display_match_review, reassign_salary_match, reject_salary_match,
+    },
+    salary::process_salaries,
+    server::ServerState,
+};
+use pretty_assertions::assert_eq;
+use tiny_http::{Method, Request, StatusCode, TestRequest};
+
+const SALARY_FORM: &str = "year=2023&name=Doe%2C+Jane&department=WL+-+Mathematics";
+
+// Two students who could both be the "Doe, Jane" on an unmatched 2023 salary row
+fn build_state() -> Arc<ServerState> {
+    let connection_pool = common::build_connection_pool();
+    common::seed_directory(&connection_pool);
+
+    common::build_state(connection_pool)
+}
+
+fn build_request(body: &'static str) -> Request {
+    TestRequest::new()
+        .with_method(Method::Post)
+        .with_path("/admin/matches/confirm")
+        .with_body(body)
+        .into()
+}
+
+// (StudentId, AmountUsd, Method) of every salary stored for the row
+fn fetch_salaries(state: &Arc<ServerState>) -> Vec<(String, usize, String)> {
+    let connection = state.connection_pool.get().unwrap();
+    let mut statement = connection
+        .prepare(
+            "SELECT StudentId, AmountUsd, Method FROM Salaries
+            WHERE Year = 2023 AND SalaryName = 'Doe, Jane'",
+        )
+        .unwrap();
+    let salaries = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .flatten()
+        .collect();
+
+    salaries
+}
+
+// (StudentId, Action) of the stored override for the row
+fn fetch_override(state: &Arc<ServerState>) -> (Option<String>, String) {
+    state
+        .connection_pool
+        .get()
+        .unwrap()
+        .query_row(
+            "SELECT StudentId, Action FROM SalaryMatchOverrides
+            WHERE Year = 2023 AND Name = 'Doe, Jane' AND Department = 'WL - Mathematics'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+}
+
+fn count_unmatched(state: &Arc<ServerState>) -> usize {
+    state
+        .connection_pool
+        .get()
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM UnmatchedSalaries", [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+}
+
+fn write_salaries_directory() -> String {
+    let directory = temp_dir().join(format!("perdue-overrides-{}", generate_id()));
+    create_dir_all(&directory).unwrap();
+    write(
+        directory.join("perdue_salaries_2023.csv"),
+        "\u{feff}Year,Name,Department,JobTitle,City,TotalCompensation
+Compensation in 2023,\"Doe, Jane\",WL - Mathematics,Graduate Student,West Lafayette,\"$31,250\"
+",
+    )
+    .unwrap();
+
+    directory.to_string_lossy().to_string()
+}
+
+#[test]
+fn confirm_salary_match_stores_the_salary_and_the_override() {
+    let state = build_state();
+    let mut request =
+        build_request("year=2023&name=Doe%2C+Jane&department=WL+-+Mathematics&student_id=jdoe");
+
+    let response = confirm_salary_match(&mut request, &state).unwrap();
+
+    assert_eq!(response.status_code(), StatusCode(200));
+    assert_eq!(
+        fetch_salaries(&state),
+        vec![(String::from("jdoe"), 3125000, String::from("Confirmed"))]
+    );
+    assert_eq!(
+        fetch_override(&state),
+        (Some(String::from("jdoe")), String::from("Confirmed"))
+    );
+    assert_eq!(count_unmatched(&state), 0);
+}
+
+#[test]
+fn reassign_and_reject_salary_match_replace_the_previous_override() {
+    let state = build_state();
+    let mut confirm_request =
+        build_request("year=2023&name=Doe%2C+Jane&department=WL+-+Mathematics&student_id=jdoe");
+    let mut reassign_request =
+        build_request("year=2023&name=Doe%2C+Jane&department=WL+-+Mathematics&student_id=jdoe2");
+    let mut reject_request =
+        build_request("year=2023&name=Doe%2C+Jane&department=WL+-+Mathematics&student_id=jdoe2");
+
+    confirm_salary_match(&mut confirm_request, &state).unwrap();
+    reassign_salary_match(&mut reassign_request, &state).unwrap();
+
+    assert_eq!(
+        fetch_salaries(&state),
+        vec![(String::from("jdoe2"), 3125000, String::from("Reassigned"))]
+    );
+    assert_eq!(
+        fetch_override(&state),
+        (Some(String::from("jdoe2")), String::from("Reassigned"))
+    );
+
+    reject_salary_match(&mut reject_request, &state).unwrap();
+
+    assert_eq!(fetch_salaries(&state), vec![]);
+    assert_eq!(fetch_override(&state), (None, String::from("Rejected")));
+}
+
+// The kind of error a confirmation fails with
+fn confirm_error(body: &'static str, state: &Arc<ServerState>) -> &'static str {
+    match confirm_salary_match(&mut build_request(body), state) {
+        Ok(_) => panic!("Confirmation should fail"),
+        Err(status) => status.kind(),
+    }
+}
+
+#[test]
+fn confirm_salary_match_rejects_unknown_salaries_and_students() {
+    let state = build_state();
+
+    assert_eq!(confirm_error(SALARY_FORM, &state), "InvalidArgument");
+    assert_eq!(
+        confirm_error(
+            "year=2023&name=Doe%2C+Jane&department=WL+-+Mathematics&student_id=nobody",
+            &state
+        ),
+        "InvalidArgument"
+    );
+    assert_eq!(
+        confirm_error(
+            "year=2022&name=Doe%2C+Jane&department=WL+-+Mathematics&student_id=jdoe",
+            &state
+        ),
+        "NotFound"
+    );
+    assert_eq!(count_unmatched(&state), 1);
+}
+
+#[test]
+fn reassign_salary_match_rejects_a_student_already_paid_that_year() {
+    let state = build_state();
+    let mut request =
+        build_request("year=2023&name=Doe%2C+Jane&department=WL+-+Mathematics&student_id=alee");
+
+    let error = match reassign_salary_match(&mut request, &state) {
+        Ok(_) => panic!("Reassignment should fail"),
+        Err(status) => status.kind(),
+    };
+    let alee_salary: usize = state
+        .connection_pool
+        .get()
+        .unwrap()
+        .query_row(
+            "SELECT AmountUsd FROM Salaries WHERE StudentId = 'alee' AND Year = 2023",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+
+    assert_eq!(error, "InvalidArgument");
+    assert_eq!(alee_salary, 3125050);
+    assert_eq!(fetch_salaries(&state), vec![]);
+    assert_eq!(count_unmatched(&state), 1);
+}
+
+#[test]
+fn process_salaries_keeps_overrides_from_earlier_reviews() {
+    let state = build_state();
+    let salaries_directory = write_salaries_directory();
+    let mut reassign_request =
+        build_request("year=2023&name=Doe%2C+Jane&department=WL+-+Mathematics&student_id=jdoe2");
+
+    let before_review = process_salaries(&state.connection_pool, &salaries_directory);
+    reassign_salary_match(&mut reassign_request, &state).unwrap();
+    let after_reassign = process_salaries(&state.connection_pool, &salaries_directory);
+    let mut reject_request = build_request(SALARY_FORM);
+    reject_salary_match(&mut reject_request, &state).unwrap();
+    let after_reject = process_salaries(&state.connection_pool, &salaries_directory);
+
+    assert_eq!(before_review.salaries.len(), 0);
+    assert_eq!(before_review.ambiguous.len(), 1);
+    assert_eq!(
+        after_reassign
+            .salaries
+            .iter()
+            .map(|salary| (
+                salary.student_id.as_str(),
+                salary.amount_usd,
+                salary.method.as_str()
+            ))
+            .collect::<Vec<_>>(),
+        vec![("jdoe2", 3125000, "Reassigned")]
+    );
+    assert_eq!(after_reassign.ambiguous.len(), 0);
+    assert_eq!(after_reject.salaries.len(), 0);
+    assert_eq!(after_reject.ambiguous.len(), 0);
+    assert_eq!(after_reject.unmatched.len(), 0);
+}
+
+fn display_review(path: &str, state: &Arc<ServerState>) -> String {
+    let request: Request = TestRequest::new().with_path(path).into();
+    let response = match display_match_review(&request, state) {
+        Ok(response) => response,
+        Err(status) => panic!("Review queue should render: {}", status.message()),
+    };
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body).unwrap();
+
+    body
+}
+
+#[test]
+fn display_match_review_searches_wildcards_literally() {
+    let state = build_state();
+
+    assert!(display_review("/admin/matches?search=Doe", &state).contains("Doe, Jane"));
+    assert!(!display_review("/admin/matches?search=_", &state).contains("Doe, Jane"));
+    assert!(!display_review("/admin/matches?search=%25", &state).contains("Doe, Jane"));
+}
@@ -0,0 +1,70 @@
+use perdue::matching::{normalize_name, MatchMethod, SalaryMatch, StudentIndex};
+use pretty_assertions::assert_eq;
+
+fn student(id: &str, name: &str, department: &str) -> (String, String, String) {
+    (id.to_string(), name.to_string(), department.to_string())
+}
+
+#[test]
+fn normalize_name_strips_diacritics_suffixes_and_hyphens() {
+    assert_eq!(
+        normalize_name("José  García-Núñez Jr."),
+        vec!["jose", "garcia", "nunez"]
+    );
+}
+
+#[test]
+fn match_salary_handles_initials_and_hyphenated_family_names() {
+    let index = StudentIndex::new(vec![
+        student("1", "Jane Garcia-Lopez", "Department of Computer Science"),
+        student("2", "Robert Smith", "Department of Mathematics"),
+    ]);
+
+    let SalaryMatch::Matched(candidate) =
+        index.match_salary("Garcia-Lopez, J", "WL - Computer Science")
+    else {
+        panic!("Expected a match");
+    };
+
+    assert_eq!(candidate.student_id, "1");
+    assert_eq!(candidate.method, MatchMethod::Initial);
+    assert_eq!(
+        index.match_salary("Smyth, Robert", "WL - Mathematics"),
+        SalaryMatch::Unmatched
+    );
+}
+
+#[test]
+fn match_salary_prefers_consistent_department() {
+    let index = StudentIndex::new(vec![
+        student("1", "Wei Zhang", "Department of Mathematics"),
+        student(
+            "2",
+            "Wei Zhang",
+            "School of Electrical and Computer Engineering",
+        ),
+    ]);
+
+    let SalaryMatch::Matched(candidate) =
+        index.match_salary("Zhang, Wei", "WL - Electrical &amp; Com")
+    else {
+        panic!("Expected a match");
+    };
+
+    assert_eq!(candidate.student_id, "2");
+}
+
+#[test]
+fn match_salary_flags_ambiguous_candidates() {
+    let index = StudentIndex::new(vec![
+        student("1", "Wei Zhang", "Department of Mathematics"),
+        student("2", "Wei Zhang", "Department of Statistics"),
+    ]);
+
+    let SalaryMatch::Ambiguous(candidates) = index.match_salary("Zhang, Wei", "WL - Chemistry")
+    else {
+        panic!("Expected an ambiguous match");
+    };
+
+    assert_eq!(candidates.len(), 2);
+}
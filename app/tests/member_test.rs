@@ -0,0 +1,108 @@
+mod common;
+
+use std::{io::Read, sync::Arc};
+
+use perdue::{error::Status, member::display_member, server::ServerState};
+use pretty_assertions::assert_eq;
+use tiny_http::{Request, StatusCode, TestRequest};
+
+fn build_state() -> Arc<ServerState> {
+    let connection_pool = common::build_connection_pool();
+    common::seed_directory(&connection_pool);
+
+    common::build_state(connection_pool)
+}
+
+fn display(path: &str, state: &Arc<ServerState>) -> (StatusCode, String) {
+    let request: Request = TestRequest::new().with_path(path).into();
+    let response = match display_member(&request, state) {
+        Ok(response) => response,
+        Err(status) => panic!("Member page should render: {}", status.message()),
+    };
+    let status_code = response.status_code();
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body).unwrap();
+
+    (status_code, body)
+}
+
+// The department median and position cells of the salary table row for a year
+fn find_department_cells(body: &str, year: usize) -> Vec<String> {
+    let year_cell = format!(">{}</td>", year);
+    let row = body
+        .split("<tr>")
+        .find(|row| row.contains(&year_cell))
+        .unwrap_or_else(|| panic!("Missing salary row for {}", year));
+    let cells: Vec<String> = row
+        .split("</td>")
+        .filter_map(|cell| cell.rsplit_once('>'))
+        .map(|(_, text)| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .collect();
+
+    cells[cells.len() - 2..].to_vec()
+}
+
+#[test]
+fn display_member_places_salaries_within_the_college_department() {
+    let state = build_state();
+
+    let (status_code, body) = display("/member/alee", &state);
+
+    assert_eq!(status_code, StatusCode(200));
+    assert!(body.contains("Ann Lee"));
+    assert_eq!(
+        find_department_cells(&body, 2023),
+        ["$20,000.00", "Paid more than 2 of 2 others"]
+    );
+    assert_eq!(
+        find_department_cells(&body, 2022),
+        ["$22,500.00", "Paid more than 1 of 1 other"]
+    );
+}
+
+#[test]
+fn display_member_counts_equal_pay_as_not_paid_more() {
+    let state = build_state();
+
+    let (_, body) = display("/member/bkim", &state);
+
+    assert_eq!(
+        find_department_cells(&body, 2023),
+        ["$20,000.00", "Paid more than 1 of 2 others"]
+    );
+    assert_eq!(
+        find_department_cells(&body, 2022),
+        ["$22,500.00", "Paid more than 0 of 1 other"]
+    );
+}
+
+#[test]
+fn display_member_does_not_compare_a_student_alone_in_their_department() {
+    let state = build_state();
+
+    let (_, body) = display("/member/egarcia", &state);
+
+    assert_eq!(
+        find_department_cells(&body, 2023),
+        ["$28,000.00", "Only student paid in the department"]
+    );
+}
+
+#[test]
+fn display_member_rejects_unknown_students() {
+    let state = build_state();
+
+    for path in ["/member/nobody", "/member/", "/member/alee/salaries"] {
+        let request: Request = TestRequest::new().with_path(path).into();
+
+        assert!(
+            matches!(
+                display_member(&request, &state).map(|_| ()),
+                Err(Status::NotFound(_))
+            ),
+            "{} should not be found",
+            path
+        );
+    }
+}
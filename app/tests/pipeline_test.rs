@@ -0,0 +1,198 @@
+use std::sync::{atomic::AtomicUsize, Arc};
+
+use common::{build_configuration, build_connection_pool};
+use mock_http::TestServer;
+use perdue::{
+    pipeline::run_pipeline, registry::CollegeSource, server::ServerState, shutdown::Shutdown,
+};
+use pretty_assertions::assert_eq;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::params;
+use serde_json::json;
+use tiny_http::Response;
+
+mod common;
+
+fn liberal_arts_page(department: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(format!(
+        r#"
+        <!DOCTYPE html>
+        <html>
+            <body>
+                <table>
+                    <tbody>
+                        <tr class="hidden profile-row">
+                            <td><a href="profiles/adam-kotanko.html">Adam Kotanko</a></td>
+                            <td>Graduate Student // {}</td>
+                            <td>&nbsp;</td>
+                            <td>akotanko@purdue.edu</td>
+                            <td>&nbsp;</td>
+                        </tr>
+                    </tbody>
+                </table>
+            </body>
+        </html>"#,
+        department
+    ))
+}
+
+fn build_source(source: serde_json::Value) -> CollegeSource {
+    serde_json::from_value(source).unwrap()
+}
+
+fn build_pipeline_state(
+    connection_pool: Pool<SqliteConnectionManager>,
+    sources: Vec<CollegeSource>,
+) -> Arc<ServerState> {
+    let mut configuration = build_configuration();
+    configuration.files.salaries_directory = String::from("tests/no-salaries");
+
+    Arc::new(ServerState {
+        connection_pool,
+        configuration,
+        sources,
+        live_workers: AtomicUsize::new(0),
+    })
+}
+
+fn fetch_run_status(run_id: &str, connection_pool: &Pool<SqliteConnectionManager>) -> String {
+    connection_pool
+        .get()
+        .unwrap()
+        .query_row(
+            "SELECT Status FROM PipelineRuns WHERE RunId = ?1",
+            params![run_id],
+            |row| row.get(0),
+        )
+        .unwrap()
+}
+
+// (CollegeId, Scraped, Errors, Inserted, Updated) for every college in a run
+fn fetch_run_colleges(
+    run_id: &str,
+    connection_pool: &Pool<SqliteConnectionManager>,
+) -> Vec<(String, usize, usize, usize, usize)> {
+    let connection = connection_pool.get().unwrap();
+    let mut statement = connection
+        .prepare(
+            "SELECT CollegeId, Scraped, Errors, Inserted, Updated FROM PipelineRunColleges
+            WHERE RunId = ?1 ORDER BY CollegeId",
+        )
+        .unwrap();
+
+    statement
+        .query_map(params![run_id], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+            ))
+        })
+        .unwrap()
+        .map(|row| row.unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn should_record_a_panicking_scraper_as_a_failed_college() {
+    let liberal_arts_server = TestServer::new();
+    liberal_arts_server.add_response(liberal_arts_page("Sociology"));
+    let broken_server = TestServer::new();
+    broken_server.add_response(Response::from_string("<html><body></body></html>"));
+    let connection_pool = build_connection_pool();
+    let state = build_pipeline_state(
+        connection_pool.clone(),
+        vec![
+            build_source(json!({
+                "id": "liberal-arts",
+                "name": "Liberal Arts",
+                "base_url": liberal_arts_server.url(),
+                "default_department": "",
+                "scraper": "LiberalArts"
+            })),
+            build_source(json!({
+                "id": "broken",
+                "name": "Broken",
+                "base_url": broken_server.url(),
+                "default_department": "",
+                "scraper": "SinglePage",
+                "parser": "Default",
+                "selectors": {
+                    "directory_row_selector": "<<<",
+                    "name_selectors": []
+                }
+            })),
+        ],
+    );
+    let (_sender, shutdown) = Shutdown::new();
+
+    let run_id = run_pipeline(&state, &shutdown).await.unwrap();
+
+    assert_eq!(fetch_run_status(&run_id, &connection_pool), "Failed");
+    assert_eq!(
+        fetch_run_colleges(&run_id, &connection_pool),
+        vec![
+            (String::from("broken"), 0, 1, 0, 0),
+            (String::from("liberal-arts"), 1, 0, 1, 0),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn should_only_count_changed_students_as_updated() {
+    let server = TestServer::new();
+    server.add_response(liberal_arts_page("Sociology"));
+    server.add_response(liberal_arts_page("Sociology"));
+    server.add_response(liberal_arts_page("History"));
+    let connection_pool = build_connection_pool();
+    let state = build_pipeline_state(
+        connection_pool.clone(),
+        vec![build_source(json!({
+            "id": "liberal-arts",
+            "name": "Liberal Arts",
+            "base_url": server.url(),
+            "default_department": "",
+            "scraper": "LiberalArts"
+        }))],
+    );
+    let (_sender, shutdown) = Shutdown::new();
+
+    let first_run_id = run_pipeline(&state, &shutdown).await.unwrap();
+    let unchanged_run_id = run_pipeline(&state, &shutdown).await.unwrap();
+    let changed_run_id = run_pipeline(&state, &shutdown).await.unwrap();
+
+    assert_eq!(
+        fetch_run_status(&first_run_id, &connection_pool),
+        "Succeeded"
+    );
+    assert_eq!(
+        fetch_run_colleges(&first_run_id, &connection_pool),
+        vec![(String::from("liberal-arts"), 1, 0, 1, 0)]
+    );
+    assert_eq!(
+        fetch_run_colleges(&unchanged_run_id, &connection_pool),
+        vec![(String::from("liberal-arts"), 1, 0, 0, 0)]
+    );
+    assert_eq!(
+        fetch_run_colleges(&changed_run_id, &connection_pool),
+        vec![(String::from("liberal-arts"), 1, 0, 0, 1)]
+    );
+}
+
+#[tokio::test]
+async fn should_return_an_error_when_the_run_cannot_be_recorded() {
+    // No migrations are applied, so there is no PipelineRuns table to record the run in
+    let connection_pool = Pool::builder()
+        .max_size(1)
+        .build(SqliteConnectionManager::memory())
+        .unwrap();
+    let state = build_pipeline_state(connection_pool, vec![]);
+    let (_sender, shutdown) = Shutdown::new();
+
+    let run_id = run_pipeline(&state, &shutdown).await;
+
+    assert!(run_id.is_err());
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use perdue::raises::{compute_compensation_changes, fetch_department_raises, CompensationChange};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn compute_compensation_changes_adjusts_for_inflation() {
+    let cpi = BTreeMap::from([(2022, 200.0), (2023, 210.0)]);
+    let changes = compute_compensation_changes(&[(2022, 2_000_000), (2023, 2_200_000)], &cpi);
+
+    assert_eq!(changes[0].change_cents, None);
+    assert_eq!(changes[1].change_cents, Some(200_000));
+    assert_eq!(changes[1].real_change_cents, Some(100_000));
+    assert!((changes[1].change_ratio.unwrap() - 0.1).abs() < 1e-9);
+    assert!((changes[1].real_change_ratio.unwrap() - 1.0 / 21.0).abs() < 1e-9);
+}
+
+#[test]
+fn compute_compensation_changes_skips_gaps_and_missing_cpi() {
+    let cpi = BTreeMap::from([(2020, 100.0), (2023, 120.0)]);
+    let changes = compute_compensation_changes(
+        &[(2020, 1_000_000), (2022, 1_100_000), (2023, 1_000_000)],
+        &cpi,
+    );
+
+    assert_eq!(
+        changes[1],
+        CompensationChange {
+            year: 2022,
+            amount_usd: 1_100_000,
+            change_cents: None,
+            change_ratio: None,
+            real_change_cents: None,
+            real_change_ratio: None,
+        }
+    );
+    assert_eq!(changes[2].change_cents, Some(-100_000));
+    assert_eq!(changes[2].real_change_cents, None);
+}
+
+#[test]
+fn fetch_department_raises_keeps_same_named_departments_of_colleges_apart() {
+    let connection_pool = common::build_connection_pool();
+    common::seed_directory(&connection_pool);
+    let connection = connection_pool.get().unwrap();
+    connection
+        .execute(
+            "INSERT INTO Salaries (StudentId, Year, AmountUsd) VALUES ('alee', 2021, 2000000)",
+            [],
+        )
+        .unwrap();
+
+    let raises = fetch_department_raises(&BTreeMap::new(), &connection).unwrap();
+
+    assert_eq!(
+        raises
+            .iter()
+            .map(|raise| (
+                raise.college_id.as_str(),
+                raise.department.as_str(),
+                raise.year,
+                raise.student_count,
+                raise.raise_count,
+                raise.median_change_cents
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            ("engineering", "Physics", 2023, 1, 0, Some(0.0)),
+            ("science", "Physics", 2023, 2, 1, Some(312525.0)),
+            ("science", "Physics", 2022, 1, 1, Some(500000.0)),
+        ]
+    );
+}
@@ -0,0 +1,103 @@
+use std::fs;
+
+use perdue::registry::{read_sources, ScraperKind};
+use pretty_assertions::assert_eq;
+
+fn write_sources(name: &str, sources: &str) -> String {
+    let path = std::env::temp_dir().join(name);
+    fs::write(&path, sources).unwrap();
+
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn read_committed_sources() {
+    let sources = read_sources("data/colleges.json").expect("Should read college sources");
+
+    assert!(sources
+        .iter()
+        .any(|source| source.scraper == ScraperKind::Agriculture));
+    assert!(sources
+        .iter()
+        .filter(|source| source.scraper == ScraperKind::SinglePage)
+        .all(|source| source.parser.is_some() && source.selectors.is_some()));
+}
+
+#[test]
+fn read_single_page_source() {
+    let path = write_sources(
+        "perdue_single_page_source.json",
+        r#"[{
+            "id": "1",
+            "name": "College of Education",
+            "base_url": "https://education.purdue.edu/graduate-directory/",
+            "default_department": "School of Education",
+            "scraper": "SinglePage",
+            "parser": "Default",
+            "selectors": {
+                "directory_row_selector": ".row",
+                "name_selectors": ["h2"],
+                "position_selector": null,
+                "department_selector": null,
+                "email_selector": "a",
+                "location_selector": null
+            }
+        }]"#,
+    );
+
+    let sources = read_sources(&path).expect("Should read college sources");
+
+    assert_eq!(sources.len(), 1);
+    assert_eq!(sources[0].college.name, "College of Education");
+    assert_eq!(sources[0].college.default_office.building, "");
+}
+
+#[test]
+fn read_source_with_unregistered_parser() {
+    let path = write_sources(
+        "perdue_unregistered_parser_source.json",
+        r#"[{
+            "id": "1",
+            "name": "College of Education",
+            "base_url": "https://education.purdue.edu/graduate-directory/",
+            "default_department": "School of Education",
+            "scraper": "SinglePage",
+            "parser": "Unknown",
+            "selectors": {
+                "directory_row_selector": ".row",
+                "name_selectors": ["h2"],
+                "position_selector": null,
+                "department_selector": null,
+                "email_selector": "a",
+                "location_selector": null
+            }
+        }]"#,
+    );
+
+    assert!(read_sources(&path).is_err());
+}
+
+#[test]
+fn read_sources_with_duplicate_ids() {
+    let path = write_sources(
+        "perdue_duplicate_id_sources.json",
+        r#"[
+            {
+                "id": "0",
+                "name": "College of Agriculture",
+                "base_url": "https://ag.purdue.edu",
+                "default_department": "School of Agriculture",
+                "scraper": "Agriculture"
+            },
+            {
+                "id": "0",
+                "name": "College of Liberal Arts",
+                "base_url": "https://cla.purdue.edu/directory/",
+                "default_department": "School of Liberal Arts",
+                "scraper": "LiberalArts"
+            }
+        ]"#,
+    );
+
+    assert!(read_sources(&path).is_err());
+}
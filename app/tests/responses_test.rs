@@ -0,0 +1,97 @@
+use perdue::responses::{fetch_contacts, write_contacts_csv, ResponsesQuery};
+use pretty_assertions::assert_eq;
+use rusqlite::Connection;
+
+fn build_connection() -> Connection {
+    let connection = Connection::open_in_memory().unwrap();
+    connection
+        .execute_batch(
+            "CREATE TABLE Students (Id TEXT, Name TEXT, Email TEXT, Department TEXT);
+            CREATE TABLE Offices (StudentId TEXT, Building TEXT, Room TEXT);
+            CREATE TABLE Salaries (StudentId TEXT, Year INTEGER, AmountUsd INTEGER);
+            CREATE TABLE Responses (ResponseId VARCHAR, StudentId VARCHAR,
+                GraduateStudent BOOLEAN, FirstAndLastName VARCHAR, AcademicUnit VARCHAR,
+                PurdueEmail VARCHAR, PersonalEmail VARCHAR, PhoneNumber VARCHAR,
+                MailingList VARCHAR, KeyIssues TEXT);
+            INSERT INTO Students VALUES ('a', 'Ann Lee', 'alee@purdue.edu', 'Physics'),
+                ('b', 'Bo Kim', 'bkim@purdue.edu', 'Physics');
+            INSERT INTO Offices VALUES ('a', 'PHYS', '101');
+            INSERT INTO Salaries VALUES ('a', 2022, 100000), ('a', 2023, 120000);
+            INSERT INTO Responses VALUES
+                ('r1', 'a', 1, 'Ann Lee', 'Physics', 'alee@purdue.edu', NULL, NULL, 'Yes',
+                    '[\"Pay\",\"Housing\"]'),
+                ('r2', NULL, 1, 'Cy Park', 'Biology', 'cpark@purdue.edu', NULL, NULL, 'No',
+                    '[\"Housing\"]'),
+                ('r3', NULL, 1, 'Di Roe', 'Biology', NULL, NULL, NULL, 'Yes', 'Housing');",
+        )
+        .unwrap();
+
+    connection
+}
+
+#[test]
+fn fetch_contacts_includes_students_without_responses_and_unlinked_responses() {
+    let connection = build_connection();
+    let contacts = fetch_contacts(&ResponsesQuery::default(), None, &connection).unwrap();
+    let names: Vec<(&str, &str)> = contacts
+        .iter()
+        .map(|contact| (contact.name.as_str(), contact.responded.as_str()))
+        .collect();
+
+    assert_eq!(
+        names,
+        vec![
+            ("Cy Park", "Yes"),
+            ("Di Roe", "Yes"),
+            ("Ann Lee", "Yes"),
+            ("Bo Kim", "No")
+        ]
+    );
+    assert_eq!(contacts[2].office, "PHYS 101");
+    assert_eq!(contacts[2].latest_compensation, "$1,200.00");
+    assert_eq!(contacts[2].key_issues, "Pay, Housing");
+}
+
+#[test]
+fn fetch_contacts_filters_by_key_issue_and_mailing_list() {
+    let connection = build_connection();
+    let query = ResponsesQuery {
+        key_issue: Some(String::from("Housing")),
+        mailing_list: Some(String::from("yes")),
+        ..ResponsesQuery::default()
+    };
+    let contacts = fetch_contacts(&query, None, &connection).unwrap();
+
+    assert_eq!(contacts.len(), 1);
+    assert_eq!(contacts[0].name, "Ann Lee");
+    assert!(fetch_contacts(
+        &ResponsesQuery {
+            key_issue: Some(String::from("Parking")),
+            ..ResponsesQuery::default()
+        },
+        None,
+        &connection
+    )
+    .is_err());
+}
+
+#[test]
+fn write_contacts_csv_quotes_cells_that_spreadsheets_would_run() {
+    let connection = build_connection();
+    connection
+        .execute_batch(
+            "UPDATE Responses SET FirstAndLastName = '=HYPERLINK(\"http://evil.com\")',
+                AcademicUnit = '@SUM(A1)', PhoneNumber = '+1 765 555 0100'
+                WHERE ResponseId = 'r2';",
+        )
+        .unwrap();
+    let contacts = fetch_contacts(&ResponsesQuery::default(), None, &connection).unwrap();
+    let csv = String::from_utf8(write_contacts_csv(&contacts).unwrap()).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(lines.len(), 5);
+    assert!(lines[1].starts_with(
+        r#""'=HYPERLINK(""http://evil.com"")",cpark@purdue.edu,,'+1 765 555 0100,'@SUM(A1),"#
+    ));
+    assert!(lines[3].starts_with("Ann Lee,alee@purdue.edu,"));
+}
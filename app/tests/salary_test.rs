@@ -0,0 +1,157 @@
+mod common;
+
+use std::{env::temp_dir, fs::write};
+
+use common::build_connection_pool;
+use perdue::salary::{
+    process_salaries, read_compensation_rows, store_salaries, ProcessedSalaries, Salary,
+    UnresolvedSalary,
+};
+use pretty_assertions::assert_eq;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+
+fn write_export(name: &str, contents: &str) -> String {
+    let path = temp_dir().join(name);
+    write(&path, contents).unwrap();
+
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn read_compensation_rows_parses_year_and_cents() {
+    let path = write_export(
+        "perdue_salaries_2021.csv",
+        "\u{feff}Year,Name,Department,JobTitle,City,TotalCompensation
+Compensation in 2021,\"Doe, Jane A\",WL - Computer Science,Graduate Student,West Lafayette,\"$31,250.5\"
+Compensation in 2021,\"Roe, Richard\",WL - Mathematics,Graduate Student,West Lafayette,$900
+",
+    );
+
+    let rows = read_compensation_rows(&path).unwrap();
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].parse_year(), Some(2021));
+    assert_eq!(rows[0].parse_amount_cents(), Some(3125050));
+    assert_eq!(rows[1].parse_amount_cents(), Some(90000));
+}
+
+#[test]
+fn process_salaries_reads_a_single_export_path() {
+    let path = write_export(
+        "perdue_salaries_single_2020.csv",
+        "Year,Name,Department,JobTitle,City,TotalCompensation
+Compensation in 2020,\"Doe, Jane A\",WL - Computer Science,Graduate Student,West Lafayette,$900
+",
+    );
+
+    let processed = process_salaries(&build_connection_pool(), &path);
+
+    assert_eq!(processed.unmatched.len(), 1);
+    assert_eq!(processed.unmatched[0].year, 2020);
+}
+
+#[test]
+fn process_salaries_sends_a_second_row_for_a_paid_student_to_review() {
+    let connection_pool = build_connection_pool();
+    common::seed_directory(&connection_pool);
+    let path = write_export(
+        "perdue_salaries_claimed_2024.csv",
+        "Year,Name,Department,JobTitle,City,TotalCompensation
+Compensation in 2024,\"Lee, Ann\",WL - Physics,Graduate Student,West Lafayette,$900
+Compensation in 2024,\"Lee, Ann\",WL - Physics and Astronomy,Graduate Student,West Lafayette,$800
+",
+    );
+
+    let processed = process_salaries(&connection_pool, &path);
+
+    assert_eq!(
+        processed
+            .salaries
+            .iter()
+            .map(|salary| (salary.student_id.as_str(), salary.amount_usd))
+            .collect::<Vec<_>>(),
+        vec![("alee", 90000)]
+    );
+    assert_eq!(processed.ambiguous.len(), 1);
+    assert_eq!(processed.ambiguous[0].amount_usd, 80000);
+    assert_eq!(processed.ambiguous[0].candidates[0].student_id, "alee");
+}
+
+#[test]
+fn read_compensation_rows_rejects_missing_columns() {
+    let path = write_export(
+        "perdue_salaries_invalid.csv",
+        "Year,Name,Department,City,TotalCompensation
+Compensation in 2021,\"Doe, Jane\",WL - Computer Science,West Lafayette,$1.00
+",
+    );
+
+    let error = read_compensation_rows(&path).unwrap_err();
+
+    assert_eq!(error.kind(), "InvalidArgument");
+}
+
+fn build_salary(student_id: &str) -> Salary {
+    Salary {
+        student_id: String::from(student_id),
+        amount_usd: 3125000,
+        year: 2023,
+        salary_name: String::from("Doe, Jane"),
+        salary_department: String::from("WL - Mathematics"),
+        confidence: 0.9,
+        method: String::from("Exact"),
+    }
+}
+
+fn fetch_matches(connection_pool: &Pool<SqliteConnectionManager>) -> Vec<String> {
+    let connection = connection_pool.get().unwrap();
+    let mut statement = connection
+        .prepare("SELECT StudentId FROM Salaries WHERE SalaryName = 'Doe, Jane'")
+        .unwrap();
+    let matches = statement
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .flatten()
+        .collect();
+
+    matches
+}
+
+#[test]
+fn store_salaries_replaces_the_previous_match_of_a_salary_row() {
+    let connection_pool = common::build_connection_pool();
+
+    store_salaries(
+        &ProcessedSalaries {
+            salaries: vec![build_salary("jdoe")],
+            ..ProcessedSalaries::default()
+        },
+        &connection_pool,
+    );
+    store_salaries(
+        &ProcessedSalaries {
+            salaries: vec![build_salary("jdoe2")],
+            ..ProcessedSalaries::default()
+        },
+        &connection_pool,
+    );
+
+    assert_eq!(fetch_matches(&connection_pool), vec![String::from("jdoe2")]);
+
+    store_salaries(
+        &ProcessedSalaries {
+            unmatched: vec![UnresolvedSalary {
+                year: 2023,
+                name: String::from("Doe, Jane"),
+                department: String::from("WL - Mathematics"),
+                amount_usd: 3125000,
+                candidates: vec![],
+            }],
+            ..ProcessedSalaries::default()
+        },
+        &connection_pool,
+    );
+
+    assert_eq!(fetch_matches(&connection_pool), Vec::<String>::new());
+}
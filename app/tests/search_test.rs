@@ -0,0 +1,19 @@
+use perdue::search::build_search_match;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn build_search_match_prefix_matches_every_word() {
+    assert_eq!(
+        build_search_match("  jan doe@purdue.edu "),
+        Some(String::from("\"jan\"* \"doe\"* \"purdue\"* \"edu\"*"))
+    );
+}
+
+#[test]
+fn build_search_match_drops_query_syntax() {
+    assert_eq!(
+        build_search_match("\"NEAR(a* OR\" -b"),
+        Some(String::from("\"NEAR\"* \"a\"* \"OR\"* \"b\"*"))
+    );
+    assert_eq!(build_search_match(" \"*- "), None);
+}
@@ -0,0 +1,108 @@
+use std::{io::Read, str::FromStr};
+
+use anyhow::anyhow;
+use perdue::{
+    error::Status,
+    server::{render_error, serve_directory},
+};
+use pretty_assertions::assert_eq;
+use tiny_http::{Header, Request, StatusCode, TestRequest};
+
+fn build_request(path: &str) -> Request {
+    TestRequest::new().with_path(path).into()
+}
+
+fn find_header_value(headers: &[Header], field: &'static str) -> Option<String> {
+    headers
+        .iter()
+        .find(|header| header.field.equiv(field))
+        .map(|header| header.value.to_string())
+}
+
+#[test]
+fn render_error_answers_api_requests_with_json() {
+    let response = render_error(
+        &build_request("/api/v1/students/missing"),
+        Status::NotFound(anyhow!("Unknown student missing")),
+    );
+    let mut body = String::new();
+
+    assert_eq!(response.status_code(), StatusCode(404));
+    response.into_reader().read_to_string(&mut body).unwrap();
+    assert_eq!(
+        body,
+        r#"{"kind":"NotFound","message":"Unknown student missing"}"#
+    );
+}
+
+#[test]
+fn render_error_retargets_htmx_requests_and_hides_internal_errors() {
+    let request: Request = TestRequest::new()
+        .with_path("/directory")
+        .with_header(Header::from_str("HX-Request: true").unwrap())
+        .into();
+    let response = render_error(&request, Status::Internal(anyhow!("database is locked")));
+    let headers = response.headers().to_vec();
+    let mut body = String::new();
+
+    assert_eq!(response.status_code(), StatusCode(500));
+    assert_eq!(
+        find_header_value(&headers, "HX-Retarget"),
+        Some(String::from("#errors"))
+    );
+    response.into_reader().read_to_string(&mut body).unwrap();
+    assert!(body.contains("Server error"));
+    assert!(!body.contains("database is locked"));
+    assert!(!body.contains("<html"));
+}
+
+#[test]
+fn render_error_renders_pages_and_sends_anonymous_users_to_sign_in() {
+    let response = render_error(
+        &build_request("/directory?page=x"),
+        Status::InvalidArgument(anyhow!("Failed to parse page")),
+    );
+    let mut body = String::new();
+
+    assert_eq!(response.status_code(), StatusCode(400));
+    assert_eq!(find_header_value(response.headers(), "HX-Retarget"), None);
+    response.into_reader().read_to_string(&mut body).unwrap();
+    assert!(body.contains("<html"));
+    assert!(body.contains("Failed to parse page"));
+
+    let response = render_error(
+        &build_request("/admin/responses?status=Yes"),
+        Status::Unauthenticated(anyhow!("Sign in")),
+    );
+
+    assert_eq!(response.status_code(), StatusCode(303));
+    assert_eq!(
+        find_header_value(response.headers(), "Location"),
+        Some(String::from(
+            "/login?next=%2Fadmin%2Fresponses%3Fstatus%3DYes"
+        ))
+    );
+}
+
+#[test]
+fn serve_directory_serves_files_and_rejects_unknown_paths() {
+    assert!(serve_directory(&build_request("/assets/main.css"), "/assets", "assets").is_ok());
+    assert!(serve_directory(&build_request("/assets/main.css?v=2"), "/assets", "assets").is_ok());
+
+    for path in [
+        "/assets/missing.css",
+        "/assets/",
+        "/assets/../Cargo.toml",
+        "/assets//etc/passwd",
+        "/assetsmain.css",
+    ] {
+        assert!(
+            matches!(
+                serve_directory(&build_request(path), "/assets", "assets"),
+                Err(Status::NotFound(_))
+            ),
+            "{} should not be served",
+            path
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use perdue::shutdown::Shutdown;
+use tokio::time::timeout;
+
+#[test]
+fn shutdown_is_seen_by_every_clone() {
+    let (sender, shutdown) = Shutdown::new();
+    let worker = shutdown.clone();
+
+    assert!(!worker.is_requested());
+    sender.send(true).unwrap();
+    assert!(shutdown.is_requested());
+    assert!(worker.is_requested());
+}
+
+#[tokio::test]
+async fn requested_waits_for_the_shutdown() {
+    let (sender, mut shutdown) = Shutdown::new();
+
+    assert!(timeout(Duration::from_millis(50), shutdown.requested())
+        .await
+        .is_err());
+    sender.send(true).unwrap();
+    assert!(timeout(Duration::from_millis(50), shutdown.requested())
+        .await
+        .is_ok());
+}
@@ -0,0 +1,90 @@
+use perdue::sign_up::{store_sign_up, SignUp, SignUpForm};
+use pretty_assertions::assert_eq;
+use rusqlite::Connection;
+
+fn build_form() -> SignUpForm {
+    SignUpForm {
+        first_and_last_name: String::from(" Jane Doe "),
+        purdue_email: String::from("JDoe@Purdue.edu"),
+        graduate_student: Some(String::from("yes")),
+        academic_unit: String::from("Mathematics"),
+        personal_email: String::new(),
+        phone_number: String::from("(765) 555-0100"),
+        mailing_list: Some(String::from("yes")),
+    }
+}
+
+#[test]
+fn validate_normalizes_the_sign_up() {
+    let sign_up = build_form()
+        .validate(vec![String::from("Housing"), String::from("Pay")])
+        .unwrap();
+
+    assert_eq!(
+        sign_up,
+        SignUp {
+            first_and_last_name: String::from("Jane Doe"),
+            purdue_email: String::from("jdoe@purdue.edu"),
+            graduate_student: true,
+            academic_unit: String::from("Mathematics"),
+            personal_email: None,
+            phone_number: Some(String::from("(765) 555-0100")),
+            mailing_list: true,
+            key_issues: vec![String::from("Pay"), String::from("Housing")],
+        }
+    );
+}
+
+#[test]
+fn validate_rejects_invalid_answers() {
+    let cases = [
+        SignUpForm {
+            purdue_email: String::from("jdoe@gmail.com"),
+            ..build_form()
+        },
+        SignUpForm {
+            phone_number: String::from("555-0100"),
+            ..build_form()
+        },
+        SignUpForm {
+            graduate_student: None,
+            ..build_form()
+        },
+    ];
+
+    for form in cases {
+        assert!(form.validate(vec![]).is_err());
+    }
+
+    assert!(build_form()
+        .validate(vec![String::from("Parking")])
+        .is_err());
+}
+
+#[test]
+fn store_sign_up_links_students_and_ignores_duplicates() {
+    let connection = Connection::open_in_memory().unwrap();
+    connection
+        .execute_batch(
+            "CREATE TABLE Students (Id TEXT, Email TEXT);
+            CREATE TABLE Responses (ResponseId VARCHAR PRIMARY KEY, Timestamp INTEGER,
+                StudentId VARCHAR, GraduateStudent BOOLEAN, FirstAndLastName VARCHAR,
+                AcademicUnit VARCHAR, PersonalEmail VARCHAR, PhoneNumber VARCHAR,
+                MailingList VARCHAR, KeyIssues TEXT, PurdueEmail VARCHAR);
+            CREATE UNIQUE INDEX ResponsesByPurdueEmail ON Responses (PurdueEmail);
+            INSERT INTO Students VALUES ('s1', 'jdoe@purdue.edu');",
+        )
+        .unwrap();
+    let sign_up = build_form().validate(vec![]).unwrap();
+
+    assert!(store_sign_up(&sign_up, &connection).unwrap().is_some());
+    assert_eq!(store_sign_up(&sign_up, &connection).unwrap(), None);
+    assert_eq!(
+        connection
+            .query_row("SELECT StudentId, COUNT(*) FROM Responses", [], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, usize>(1)?))
+            })
+            .unwrap(),
+        (String::from("s1"), 1)
+    );
+}
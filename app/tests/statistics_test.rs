@@ -0,0 +1,96 @@
+use perdue::statistics::{
+    build_histogram, fetch_compensation_statistics, histogram_bin_width, summarize_sorted,
+    CompensationStatistics, CompensationSummary, StatisticsGrouping, HISTOGRAM_BINS,
+    HISTOGRAM_HEIGHT, HISTOGRAM_WIDTH,
+};
+use pretty_assertions::assert_eq;
+use rusqlite::Connection;
+
+#[test]
+fn summarize_sorted_averages_the_middle_of_even_counts() {
+    assert_eq!(
+        summarize_sorted(&[100, 200, 400, 900]),
+        Some(CompensationSummary {
+            count: 4,
+            min: 100,
+            median: 300,
+            max: 900,
+        })
+    );
+    assert_eq!(
+        summarize_sorted(&[5, 7, 20]).map(|summary| summary.median),
+        Some(7)
+    );
+}
+
+#[test]
+fn summarize_sorted_skips_empty_amounts() {
+    assert_eq!(summarize_sorted(&[]), None);
+}
+
+#[test]
+fn fetch_compensation_statistics_computes_quartiles_in_sql() {
+    let connection = Connection::open_in_memory().unwrap();
+    connection
+        .execute_batch(
+            "CREATE TABLE College (Id TEXT, Name TEXT);
+            CREATE TABLE Students (Id TEXT, CollegeId TEXT, Department TEXT);
+            CREATE TABLE Salaries (StudentId TEXT, Year INTEGER, AmountUsd INTEGER);
+            INSERT INTO College VALUES ('1', 'Science');
+            INSERT INTO Students VALUES ('a', '1', 'Physics'), ('b', '1', 'Physics'),
+                ('c', '1', 'Physics'), ('d', '1', 'Physics'), ('e', '1', 'Biology');
+            INSERT INTO Salaries VALUES ('a', 2023, 100), ('b', 2023, 200), ('c', 2023, 400),
+                ('d', 2023, 900), ('e', 2023, 300), ('a', 2022, 50);",
+        )
+        .unwrap();
+
+    let statistics =
+        fetch_compensation_statistics(StatisticsGrouping::Department, Some(2023), 250, &connection)
+            .unwrap();
+
+    assert_eq!(
+        statistics,
+        vec![
+            CompensationStatistics {
+                group: String::from("Biology"),
+                count: 1,
+                mean: 300.0,
+                lower_quartile: 300.0,
+                median: 300.0,
+                upper_quartile: 300.0,
+                below_living_wage: 0.0,
+            },
+            CompensationStatistics {
+                group: String::from("Physics"),
+                count: 4,
+                mean: 400.0,
+                lower_quartile: 150.0,
+                median: 300.0,
+                upper_quartile: 650.0,
+                below_living_wage: 0.5,
+            },
+        ]
+    );
+}
+
+#[test]
+fn build_histogram_scales_bars_and_marks_the_living_wage() {
+    let bin_width = histogram_bin_width(3_999_999);
+    let mut counts = vec![0; HISTOGRAM_BINS];
+    counts[0] = 2;
+    counts[HISTOGRAM_BINS - 1] = 4;
+    let histogram = build_histogram(
+        String::from("All"),
+        &counts,
+        bin_width,
+        1_000_000,
+        String::from("Living wage"),
+    );
+
+    assert_eq!(bin_width, 200_000);
+    assert_eq!(histogram_bin_width(4_000_000), 300_000);
+    assert_eq!(histogram.bars[0].height, HISTOGRAM_HEIGHT / 2);
+    assert_eq!(histogram.bars[HISTOGRAM_BINS - 1].y, 0);
+    assert_eq!(histogram.marker_x, Some(HISTOGRAM_WIDTH / 4));
+    assert_eq!(histogram.max_label, "$40,000.00");
+}
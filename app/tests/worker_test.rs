@@ -0,0 +1,133 @@
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{atomic::AtomicUsize, atomic::Ordering, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+use perdue::{
+    configuration::Configuration,
+    server::{start_server_with_router, ServerState},
+    shutdown::Shutdown,
+};
+use pretty_assertions::assert_eq;
+use tiny_http::{Request, Response};
+
+// A panic whose payload panics again when it is dropped, which unwinds past the per-request
+// catch and takes the worker down with it
+struct PanicOnDrop;
+
+impl Drop for PanicOnDrop {
+    fn drop(&mut self) {
+        panic!("Dropped a panic payload");
+    }
+}
+
+fn test_router(request: &mut Request, _state: &Arc<ServerState>) -> Response<Box<dyn Read + Send>> {
+    match request.url() {
+        "/panic" => panic!("Route panicked"),
+        "/kill" => std::panic::panic_any(PanicOnDrop),
+        _ => Response::from_string("ok").boxed(),
+    }
+}
+
+// Binds to a free port up front since the server does not report the one it listens on
+fn build_state(workers: usize) -> (Arc<ServerState>, u16) {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut configuration = common::build_configuration();
+    configuration.port = port as u32;
+    configuration.workers = workers;
+
+    (
+        Arc::new(ServerState {
+            connection_pool: common::build_connection_pool(),
+            configuration,
+            sources: vec![],
+            live_workers: AtomicUsize::new(0),
+        }),
+        port,
+    )
+}
+
+// Sends a GET and returns the status code of the response
+fn get(port: u16, path: &str) -> u16 {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        path
+    )
+    .unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response);
+
+    response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .unwrap_or(0)
+}
+
+fn wait_for_live_workers(state: &ServerState, expected: usize) -> usize {
+    let started_at = Instant::now();
+
+    while state.live_workers.load(Ordering::SeqCst) != expected
+        && started_at.elapsed() < Duration::from_secs(5)
+    {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    state.live_workers.load(Ordering::SeqCst)
+}
+
+#[test]
+fn panicking_route_answers_500_and_the_worker_keeps_serving() {
+    let (state, port) = build_state(1);
+    let (sender, shutdown) = Shutdown::new();
+    let supervisor = start_server_with_router(state.clone(), shutdown, test_router);
+
+    assert_eq!(wait_for_live_workers(&state, 1), 1);
+    assert_eq!(get(port, "/panic"), 500);
+    assert_eq!(get(port, "/"), 200);
+    assert_eq!(state.live_workers.load(Ordering::SeqCst), 1);
+
+    sender.send(true).unwrap();
+    supervisor.join().unwrap();
+    assert_eq!(state.live_workers.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn supervisor_replaces_a_worker_that_died() {
+    let (state, port) = build_state(1);
+    let (sender, shutdown) = Shutdown::new();
+    let supervisor = start_server_with_router(state.clone(), shutdown, test_router);
+
+    assert_eq!(wait_for_live_workers(&state, 1), 1);
+    get(port, "/kill");
+    // Only a replacement worker can answer once the single worker is gone
+    assert_eq!(get(port, "/"), 200);
+    assert_eq!(state.live_workers.load(Ordering::SeqCst), 1);
+
+    sender.send(true).unwrap();
+    supervisor.join().unwrap();
+}
+
+#[test]
+fn workers_default_when_not_configured() {
+    let mut configuration = common::build_configuration_json();
+    configuration.as_object_mut().unwrap().remove("workers");
+
+    let configuration: Configuration = serde_json::from_value(configuration).unwrap();
+
+    assert_eq!(configuration.workers, 4);
+}
@@ -21,6 +21,23 @@ impl ToFlagConfiguration for MigrationFlag {
     }
 }
 
+#[derive(Debug, Hash, Eq, PartialEq, Clone)]
+enum UserFlag {
+    DatabasePath,
+    Username,
+    Role,
+}
+
+impl ToFlagConfiguration for UserFlag {
+    fn to_config(&self) -> FlagConfiguration {
+        match self {
+            UserFlag::DatabasePath => FlagConfiguration::required("database_path", None, ""),
+            UserFlag::Username => FlagConfiguration::optional("username", None, ""),
+            UserFlag::Role => FlagConfiguration::optional("role", None, ""),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug)]
 pub enum Direction {
     Up,
@@ -39,6 +56,44 @@ impl FromStr for Direction {
     }
 }
 
+#[derive(PartialEq, Debug)]
+pub enum UserAction {
+    List,
+    Add,
+    Remove,
+    SetRole,
+    SetPassword,
+}
+
+impl FromStr for UserAction {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "list" => Ok(UserAction::List),
+            "add" => Ok(UserAction::Add),
+            "remove" => Ok(UserAction::Remove),
+            "set_role" => Ok(UserAction::SetRole),
+            "set_password" => Ok(UserAction::SetPassword),
+            _ => Err(String::new()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CliCommand {
+    Migrate(CliArguments),
+    Users(UserArguments),
+}
+
+#[derive(Debug)]
+pub struct UserArguments {
+    pub action: UserAction,
+    pub database_connection: String,
+    pub username: Option<String>,
+    pub role: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct CliArguments {
     pub migration_direction: Direction,
@@ -47,8 +102,27 @@ pub struct CliArguments {
     pub target_version: Option<usize>,
 }
 
-pub fn parse_arguments() -> CliArguments {
+// Reads "up" or "down" to migrate, or "users <action>" to manage the users that can sign in
+pub fn parse_arguments() -> CliCommand {
     let mut args = std::env::args().skip(1).peekable();
+
+    if args.peek().is_some_and(|command| command == "users") {
+        args.next();
+        let action: Command<UserAction> = read_command(&mut args)
+            .expect("Expected one of list, add, remove, set_role or set_password");
+        let flags: FlagMap<UserFlag> = read_flags(
+            &mut args,
+            &vec![UserFlag::DatabasePath, UserFlag::Username, UserFlag::Role],
+        );
+
+        return CliCommand::Users(UserArguments {
+            action: action.value,
+            database_connection: flags.get(UserFlag::DatabasePath),
+            username: flags.get_optional(UserFlag::Username),
+            role: flags.get_optional(UserFlag::Role),
+        });
+    }
+
     let direction: Command<Direction> = read_command(&mut args).unwrap();
     let flags: FlagMap<MigrationFlag> = read_flags(
         &mut args,
@@ -59,10 +133,10 @@ pub fn parse_arguments() -> CliArguments {
         ],
     );
 
-    CliArguments {
+    CliCommand::Migrate(CliArguments {
         migration_direction: direction.value,
         migration_path: flags.get(MigrationFlag::MigrationPath),
         database_connection: flags.get(MigrationFlag::DatabasePath),
         target_version: flags.get_optional(MigrationFlag::TargetVersion),
-    }
+    })
 }
@@ -1,6 +1,9 @@
 use std::fs::DirEntry;
 
-use migrate::configuration::{parse_arguments, Direction};
+use migrate::{
+    configuration::{parse_arguments, CliArguments, CliCommand, Direction},
+    users::run_user_command,
+};
 use rusqlite::Connection;
 
 #[derive(Debug)]
@@ -16,8 +19,14 @@ impl Migration {
 }
 
 fn main() {
+    match parse_arguments() {
+        CliCommand::Migrate(arguments) => migrate(arguments),
+        CliCommand::Users(arguments) => run_user_command(arguments),
+    }
+}
+
+fn migrate(arguments: CliArguments) {
     println!("Migrating db...");
-    let arguments = parse_arguments();
     let database_path = arguments.database_connection;
     let migrations_directory = arguments.migration_path;
     let mut migrations: Vec<Migration> = vec![];
@@ -0,0 +1,169 @@
+use std::{
+    io::{stdin, stdout, Write},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use rand::rngs::OsRng;
+use rusqlite::{params, Connection};
+
+use crate::configuration::{UserAction, UserArguments};
+
+const ROLES: [&str; 3] = ["viewer", "organizer", "admin"];
+const MIN_PASSWORD_LENGTH: usize = 12;
+
+#[derive(Debug, PartialEq)]
+pub struct Role(String);
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let role = string.to_lowercase();
+
+        if ROLES.contains(&role.as_str()) {
+            Ok(Role(role))
+        } else {
+            Err(format!("Role must be one of {}", ROLES.join(", ")))
+        }
+    }
+}
+
+pub fn run_user_command(arguments: UserArguments) {
+    let connection = Connection::open(&arguments.database_connection).unwrap();
+
+    match arguments.action {
+        UserAction::List => list_users(&connection),
+        UserAction::Add => {
+            let username = require_username(&arguments);
+            let role = require_role(&arguments);
+            let password_hash = hash_password(&read_password());
+            connection
+                .execute(
+                    "INSERT INTO Users (Username, PasswordHash, Role, CreatedAt)
+                    VALUES (?1, ?2, ?3, ?4)",
+                    params![username, password_hash, role.0, unix_timestamp()],
+                )
+                .unwrap_or_else(|error| panic!("Failed to add {}: {}", username, error));
+            println!("Added {} as {}", username, role.0);
+        }
+        UserAction::Remove => {
+            let username = require_username(&arguments);
+            connection
+                .execute("DELETE FROM Sessions WHERE Username = ?1", [username])
+                .unwrap();
+            expect_user(
+                connection
+                    .execute("DELETE FROM Users WHERE Username = ?1", [username])
+                    .unwrap(),
+                username,
+            );
+            println!("Removed {}", username);
+        }
+        UserAction::SetRole => {
+            let username = require_username(&arguments);
+            let role = require_role(&arguments);
+            expect_user(
+                connection
+                    .execute(
+                        "UPDATE Users SET Role = ?1 WHERE Username = ?2",
+                        params![role.0, username],
+                    )
+                    .unwrap(),
+                username,
+            );
+            println!("{} is now {}", username, role.0);
+        }
+        UserAction::SetPassword => {
+            let username = require_username(&arguments);
+            let password_hash = hash_password(&read_password());
+            expect_user(
+                connection
+                    .execute(
+                        "UPDATE Users SET PasswordHash = ?1 WHERE Username = ?2",
+                        params![password_hash, username],
+                    )
+                    .unwrap(),
+                username,
+            );
+            // Signs the user out everywhere the old password was used
+            connection
+                .execute("DELETE FROM Sessions WHERE Username = ?1", [username])
+                .unwrap();
+            println!("Changed the password of {}", username);
+        }
+    }
+}
+
+pub fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string()
+}
+
+fn list_users(connection: &Connection) {
+    let mut statement = connection
+        .prepare("SELECT Username, Role FROM Users ORDER BY Username ASC")
+        .unwrap();
+    let users = statement
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .unwrap();
+
+    for user in users {
+        let (username, role) = user.unwrap();
+        println!("{}\t{}", username, role);
+    }
+}
+
+fn require_username(arguments: &UserArguments) -> &str {
+    arguments
+        .username
+        .as_deref()
+        .expect("The username flag is required")
+}
+
+fn require_role(arguments: &UserArguments) -> Role {
+    let role = arguments
+        .role
+        .as_deref()
+        .expect("The role flag is required");
+
+    role.parse().unwrap_or_else(|error| panic!("{}", error))
+}
+
+fn expect_user(changed: usize, username: &str) {
+    if changed == 0 {
+        panic!("Unknown user {}", username);
+    }
+}
+
+// Reads the password from stdin so it stays out of the shell history
+fn read_password() -> String {
+    print!("Password: ");
+    stdout().flush().unwrap();
+    let mut password = String::new();
+    stdin().read_line(&mut password).unwrap();
+    let password = password.trim_end_matches(['\r', '\n']).to_string();
+
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        panic!(
+            "Passwords must be at least {} characters",
+            MIN_PASSWORD_LENGTH
+        );
+    }
+
+    password
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}